@@ -0,0 +1,80 @@
+// cargo bench --bench priority_queue
+// 稳定版没有 #[bench]，这里直接用 Instant 计时，比较 SortedList、PairingHeap 和 std 的 BinaryHeap
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use linkedlist_rs::priority_queue::pairing_heap::PairingHeap;
+use linkedlist_rs::priority_queue::sorted_list::SortedList;
+
+fn keys(n: usize) -> Vec<u64> {
+  let mut x = 0x2545_f491_4f6c_dd1du64;
+  (0..n)
+    .map(|_| {
+      x ^= x << 13;
+      x ^= x >> 7;
+      x ^= x << 17;
+      x
+    })
+    .collect()
+}
+
+fn bench(name: &str, n: usize, mut f: impl FnMut()) {
+  const ROUNDS: u32 = 5;
+  let mut best = Duration::MAX;
+  for _ in 0..ROUNDS {
+    let start = Instant::now();
+    f();
+    best = best.min(start.elapsed());
+  }
+  println!("{:<28} n = {:>7}  {:>12.3?}", name, n, best);
+}
+
+fn main() {
+  for n in [1_000, 10_000] {
+    let keys = keys(n);
+
+    bench("SortedList push + pop", n, || {
+      let mut list = SortedList::new();
+      for &key in &keys {
+        list.insert(key);
+      }
+      while let Some(key) = list.pop_min() {
+        black_box(key);
+      }
+    });
+
+    bench("PairingHeap push + pop", n, || {
+      let mut heap = PairingHeap::new();
+      for &key in &keys {
+        heap.push(key);
+      }
+      while let Some(key) = heap.pop() {
+        black_box(key);
+      }
+    });
+
+    bench("BinaryHeap push + pop", n, || {
+      let mut heap = BinaryHeap::new();
+      for &key in &keys {
+        heap.push(Reverse(key));
+      }
+      while let Some(key) = heap.pop() {
+        black_box(key);
+      }
+    });
+
+    bench("PairingHeap decrease_key", n, || {
+      let mut heap = PairingHeap::new();
+      let handles: Vec<_> = keys.iter().map(|&key| heap.push(key)).collect();
+      for (handle, &key) in handles.iter().zip(&keys) {
+        heap.decrease_key(handle, key / 2).unwrap();
+      }
+      while let Some(key) = heap.pop() {
+        black_box(key);
+      }
+    });
+  }
+}
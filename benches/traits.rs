@@ -0,0 +1,63 @@
+// cargo bench --bench traits
+// 同一个泛型算法换不同的实现跑，比较各个链表的常数开销
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use linkedlist_rs::traits::{balanced, history, josephus, sliding_window_max};
+use linkedlist_rs::unsafe_deque_ok::unsafe_deque_ok;
+use linkedlist_rs::{deque, finger_tree, persistent_stack, stack_ok};
+
+fn values(n: usize) -> Vec<i32> {
+  let mut x = 0x2545_f491_4f6c_dd1du64;
+  (0..n)
+    .map(|_| {
+      x ^= x << 13;
+      x ^= x >> 7;
+      x ^= x << 17;
+      (x % 1_000_000) as i32
+    })
+    .collect()
+}
+
+fn bench(name: &str, n: usize, mut f: impl FnMut()) {
+  const ROUNDS: u32 = 5;
+  let mut best = Duration::MAX;
+  for _ in 0..ROUNDS {
+    let start = Instant::now();
+    f();
+    best = best.min(start.elapsed());
+  }
+  println!("{:<40} n = {:>7}  {:>12.3?}", name, n, best);
+}
+
+fn main() {
+  for n in [10_000, 100_000] {
+    let text: String = (0..n).map(|i| if i < n / 2 { '(' } else { ')' }).collect();
+    bench("balanced / stack_ok", n, || assert!(black_box(balanced::<stack_ok::List<char>>(&text))));
+    bench("balanced / unsafe_deque_ok", n, || assert!(black_box(balanced::<unsafe_deque_ok::List<char>>(&text))));
+    bench("balanced / deque", n, || assert!(black_box(balanced::<deque::List<char>>(&text))));
+
+    bench("josephus / unsafe_deque_ok", n, || {
+      black_box(josephus::<unsafe_deque_ok::List<usize>>(n, 3));
+    });
+    bench("josephus / deque", n, || {
+      black_box(josephus::<deque::List<usize>>(n, 3));
+    });
+
+    let values = values(n);
+    bench("sliding_window_max / unsafe_deque_ok", n, || {
+      black_box(sliding_window_max::<unsafe_deque_ok::List<usize>>(&values, 100));
+    });
+    bench("sliding_window_max / deque", n, || {
+      black_box(sliding_window_max::<deque::List<usize>>(&values, 100));
+    });
+
+    bench("history / persistent_stack", n, || {
+      black_box(history::<persistent_stack::List<usize>, _>(0..n));
+    });
+    bench("history / finger_tree", n, || {
+      black_box(history::<finger_tree::FingerTree<usize>, _>(0..n));
+    });
+  }
+}
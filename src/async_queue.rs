@@ -0,0 +1,756 @@
+// 异步的有界 MPMC 队列，元素挂在 unsafe_deque_ok 的链式队列上
+// 只依赖 std::task，不绑定任何执行器。Receiver/Sender 可以转换成 futures 的 Stream/Sink
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use crate::channel::{RecvError, SendError, TryRecvError};
+use crate::unsafe_deque_ok::unsafe_deque_ok::List;
+
+// 挂起的 future 按第一次注册的顺序排队，每个 future 只占一个 key：
+// 重复 poll 时替换 waker，完成或者被 drop 时移除，所以表的大小不会超过挂起的 future 个数
+struct Waiters {
+  next_key: u64,
+  waiting: BTreeMap<u64, Waker>,
+}
+
+impl Waiters {
+  fn new() -> Self {
+    Waiters { next_key: 0, waiting: BTreeMap::new() }
+  }
+
+  fn register(&mut self, key: &mut Option<u64>, waker: &Waker) {
+    let key = *key.get_or_insert_with(|| {
+      self.next_key += 1;
+      self.next_key
+    });
+    self.waiting
+      .entry(key)
+      .and_modify(|old| if !old.will_wake(waker) { old.clone_from(waker) })
+      .or_insert_with(|| waker.clone());
+  }
+
+  // future 完成了，不管有没有被唤醒过都不再排队
+  fn complete(&mut self, key: &mut Option<u64>) {
+    if let Some(key) = key.take() {
+      self.waiting.remove(&key);
+    }
+  }
+
+  // future 没有完成就被 drop 了。它已经被唤醒（不在表里）的话，这次通知还没有用上，
+  // 要转给下一个等待者，否则那个等待者会一直挂着
+  fn cancel(&mut self, key: &mut Option<u64>) -> Option<Waker> {
+    let key = key.take()?;
+    match self.waiting.remove(&key) {
+      Some(_) => None,
+      None => self.notify_one(),
+    }
+  }
+
+  // 只唤醒排在最前面的一个，被唤醒的 future 从表里移除
+  fn notify_one(&mut self) -> Option<Waker> {
+    self.waiting.pop_first().map(|(_, waker)| waker)
+  }
+
+  fn notify_all(&mut self) -> Vec<Waker> {
+    std::mem::take(&mut self.waiting).into_values().collect()
+  }
+}
+
+struct State<T> {
+  items: List<T>,
+  capacity: usize,
+  senders: usize,
+  receivers: usize,
+  // 因为队列满而挂起的发送方
+  send_waiters: Waiters,
+  // 因为队列空而挂起的接收方
+  recv_waiters: Waiters,
+}
+
+struct Shared<T> {
+  state: Mutex<State<T>>,
+}
+
+pub struct Sender<T> {
+  shared: Arc<Shared<T>>,
+}
+
+pub struct Receiver<T> {
+  shared: Arc<Shared<T>>,
+}
+
+pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+  assert!(capacity > 0, "async queue capacity must be greater than zero");
+  let shared = Arc::new(Shared {
+    state: Mutex::new(State {
+      items: List::new(),
+      capacity,
+      senders: 1,
+      receivers: 1,
+      send_waiters: Waiters::new(),
+      recv_waiters: Waiters::new(),
+    }),
+  });
+  (Sender { shared: shared.clone() }, Receiver { shared })
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum TrySendError<T> {
+  Full(T),
+  Disconnected(T),
+}
+
+impl<T> fmt::Debug for TrySendError<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TrySendError::Full(_) => f.write_str("Full(..)"),
+      TrySendError::Disconnected(_) => f.write_str("Disconnected(..)"),
+    }
+  }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TrySendError::Full(_) => f.write_str("sending on a full queue"),
+      TrySendError::Disconnected(_) => f.write_str("sending on a closed queue"),
+    }
+  }
+}
+
+impl<T> std::error::Error for TrySendError<T> {}
+
+// 唤醒都放到锁外面做
+fn wake(waker: Option<Waker>) {
+  if let Some(waker) = waker {
+    waker.wake();
+  }
+}
+
+fn wake_all(wakers: Vec<Waker>) {
+  wakers.into_iter().for_each(Waker::wake);
+}
+
+// SendFuture 和 SendSink、RecvFuture 和 RecvStream 共用的 poll 逻辑，
+// key 是调用者在等待队列里的位置
+impl<T> Shared<T> {
+  fn poll_send(&self, elem: &mut Option<T>, key: &mut Option<u64>, cx: &mut Context<'_>) -> Poll<Result<(), SendError<T>>> {
+    let mut state = self.state.lock().unwrap();
+    if state.receivers == 0 {
+      state.send_waiters.complete(key);
+      return Poll::Ready(Err(SendError(elem.take().expect("send polled after completion"))));
+    }
+    if state.items.len() == state.capacity {
+      state.send_waiters.register(key, cx.waker());
+      return Poll::Pending;
+    }
+
+    state.send_waiters.complete(key);
+    state.items.push(elem.take().expect("send polled after completion"));
+    let waker = state.recv_waiters.notify_one();
+    drop(state);
+    wake(waker);
+    Poll::Ready(Ok(()))
+  }
+
+  fn poll_recv(&self, key: &mut Option<u64>, cx: &mut Context<'_>) -> Poll<Result<T, RecvError>> {
+    let mut state = self.state.lock().unwrap();
+    match state.items.pop() {
+      Some(elem) => {
+        state.recv_waiters.complete(key);
+        let waker = state.send_waiters.notify_one();
+        drop(state);
+        wake(waker);
+        Poll::Ready(Ok(elem))
+      },
+      None if state.senders == 0 => {
+        state.recv_waiters.complete(key);
+        Poll::Ready(Err(RecvError))
+      },
+      None => {
+        state.recv_waiters.register(key, cx.waker());
+        Poll::Pending
+      }
+    }
+  }
+
+  fn cancel_send(&self, key: &mut Option<u64>) {
+    if key.is_some() {
+      let waker = self.state.lock().unwrap().send_waiters.cancel(key);
+      wake(waker);
+    }
+  }
+
+  fn cancel_recv(&self, key: &mut Option<u64>) {
+    if key.is_some() {
+      let waker = self.state.lock().unwrap().recv_waiters.cancel(key);
+      wake(waker);
+    }
+  }
+}
+
+impl<T> Sender<T> {
+  pub fn send(&self, elem: T) -> SendFuture<'_, T> {
+    SendFuture { sender: self, elem: Some(elem), key: None }
+  }
+
+  pub fn try_send(&self, elem: T) -> Result<(), TrySendError<T>> {
+    let mut state = self.shared.state.lock().unwrap();
+    if state.receivers == 0 {
+      return Err(TrySendError::Disconnected(elem));
+    }
+    if state.items.len() == state.capacity {
+      return Err(TrySendError::Full(elem));
+    }
+    state.items.push(elem);
+    let waker = state.recv_waiters.notify_one();
+    drop(state);
+    wake(waker);
+    Ok(())
+  }
+
+  pub fn into_sink(self) -> SendSink<T> {
+    SendSink { sender: Some(self), pending: None, key: None }
+  }
+}
+
+impl<T> Clone for Sender<T> {
+  fn clone(&self) -> Self {
+    self.shared.state.lock().unwrap().senders += 1;
+    Sender { shared: self.shared.clone() }
+  }
+}
+
+// 最后一个发送端断开时所有接收方都要醒过来返回 RecvError
+impl<T> Drop for Sender<T> {
+  fn drop(&mut self) {
+    let mut state = self.shared.state.lock().unwrap();
+    state.senders -= 1;
+    if state.senders == 0 {
+      let wakers = state.recv_waiters.notify_all();
+      drop(state);
+      wake_all(wakers);
+    }
+  }
+}
+
+impl<T> Receiver<T> {
+  pub fn recv(&self) -> RecvFuture<'_, T> {
+    RecvFuture { receiver: self, key: None }
+  }
+
+  pub fn try_recv(&self) -> Result<T, TryRecvError> {
+    let mut state = self.shared.state.lock().unwrap();
+    match state.items.pop() {
+      Some(elem) => {
+        let waker = state.send_waiters.notify_one();
+        drop(state);
+        wake(waker);
+        Ok(elem)
+      },
+      None if state.senders == 0 => Err(TryRecvError::Disconnected),
+      None => Err(TryRecvError::Empty),
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.shared.state.lock().unwrap().items.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.shared.state.lock().unwrap().capacity
+  }
+
+  pub fn into_stream(self) -> RecvStream<T> {
+    RecvStream { receiver: self, key: None }
+  }
+}
+
+impl<T> Clone for Receiver<T> {
+  fn clone(&self) -> Self {
+    self.shared.state.lock().unwrap().receivers += 1;
+    Receiver { shared: self.shared.clone() }
+  }
+}
+
+impl<T> Drop for Receiver<T> {
+  fn drop(&mut self) {
+    let mut state = self.shared.state.lock().unwrap();
+    state.receivers -= 1;
+    if state.receivers == 0 {
+      let items = std::mem::take(&mut state.items);
+      let wakers = state.send_waiters.notify_all();
+      drop(state);
+      drop(items);
+      wake_all(wakers);
+    }
+  }
+}
+
+pub struct SendFuture<'a, T> {
+  sender: &'a Sender<T>,
+  elem: Option<T>,
+  key: Option<u64>,
+}
+
+// 从不对 elem 做 pin projection，所以无论 T 是否 Unpin，这个 future 都可以是 Unpin
+impl<T> Unpin for SendFuture<'_, T> {}
+
+impl<T> Future for SendFuture<'_, T> {
+  type Output = Result<(), SendError<T>>;
+
+  fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    let this = self.get_mut();
+    this.sender.shared.poll_send(&mut this.elem, &mut this.key, cx)
+  }
+}
+
+impl<T> Drop for SendFuture<'_, T> {
+  fn drop(&mut self) {
+    self.sender.shared.cancel_send(&mut self.key);
+  }
+}
+
+pub struct RecvFuture<'a, T> {
+  receiver: &'a Receiver<T>,
+  key: Option<u64>,
+}
+
+impl<T> Future for RecvFuture<'_, T> {
+  type Output = Result<T, RecvError>;
+
+  fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    let this = self.get_mut();
+    this.receiver.shared.poll_recv(&mut this.key, cx)
+  }
+}
+
+impl<T> Drop for RecvFuture<'_, T> {
+  fn drop(&mut self) {
+    self.receiver.shared.cancel_recv(&mut self.key);
+  }
+}
+
+// 所有发送端断开并且队列取空之后结束
+pub struct RecvStream<T> {
+  receiver: Receiver<T>,
+  key: Option<u64>,
+}
+
+impl<T> Stream for RecvStream<T> {
+  type Item = T;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+    let this = self.get_mut();
+    this.receiver.shared.poll_recv(&mut this.key, cx).map(Result::ok)
+  }
+}
+
+impl<T> Drop for RecvStream<T> {
+  fn drop(&mut self) {
+    self.receiver.shared.cancel_recv(&mut self.key);
+  }
+}
+
+// start_send 先把元素放在 pending 里，poll_ready/poll_flush 再把它推进队列，
+// 这样多个发送端抢同一个空位时 start_send 也不会失败。
+// 接收端全部断开之后返回 SendError(())，pending 里的元素被丢弃。
+// poll_close 推完 pending 之后释放内部的 Sender，它是最后一个发送端时 Stream 随之结束；
+// 关闭之后再 poll_ready 返回 SendError(())
+pub struct SendSink<T> {
+  sender: Option<Sender<T>>,
+  pending: Option<T>,
+  key: Option<u64>,
+}
+
+impl<T> Unpin for SendSink<T> {}
+
+impl<T> SendSink<T> {
+  fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), SendError<()>>> {
+    let Some(sender) = &self.sender else {
+      return Poll::Ready(Err(SendError(())));
+    };
+    if self.pending.is_none() {
+      let closed = sender.shared.state.lock().unwrap().receivers == 0;
+      return Poll::Ready(if closed { Err(SendError(())) } else { Ok(()) });
+    }
+    sender.shared.poll_send(&mut self.pending, &mut self.key, cx).map_err(|_| SendError(()))
+  }
+}
+
+impl<T> Sink<T> for SendSink<T> {
+  type Error = SendError<()>;
+
+  fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.get_mut().poll_pending(cx)
+  }
+
+  fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+    let this = self.get_mut();
+    assert!(this.pending.is_none(), "start_send called without poll_ready");
+    if this.sender.is_none() {
+      return Err(SendError(()));
+    }
+    this.pending = Some(item);
+    Ok(())
+  }
+
+  fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.get_mut().poll_pending(cx)
+  }
+
+  fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    let this = self.get_mut();
+    if this.sender.is_none() {
+      return Poll::Ready(Ok(()));
+    }
+    let result = std::task::ready!(this.poll_pending(cx));
+    // 接收端已经断开时 pending 里的元素也一起丢掉
+    this.pending = None;
+    this.sender = None;
+    Poll::Ready(result)
+  }
+}
+
+impl<T> Drop for SendSink<T> {
+  fn drop(&mut self) {
+    if let Some(sender) = &self.sender {
+      sender.shared.cancel_send(&mut self.key);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::cell::RefCell;
+  use std::rc::Rc;
+  use std::sync::atomic::{AtomicBool, Ordering};
+  use std::task::Wake;
+  use std::thread::{self, Thread};
+
+  // 最小的执行器：当前线程阻塞直到 future 完成
+  struct ThreadWaker(Thread);
+
+  impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+      self.0.unpark();
+    }
+  }
+
+  fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+      if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+        return output;
+      }
+      thread::park();
+    }
+  }
+
+  // 单线程轮询多个任务，只有被唤醒的任务才会被再次 poll，借此检查 waker 没有丢失
+  struct Flag(AtomicBool);
+
+  impl Wake for Flag {
+    fn wake(self: Arc<Self>) {
+      self.0.store(true, Ordering::SeqCst);
+    }
+  }
+
+  type Task = (Arc<Flag>, Pin<Box<dyn Future<Output = ()>>>);
+
+  fn run(futures: Vec<Pin<Box<dyn Future<Output = ()>>>>) {
+    let mut tasks: Vec<Task> = futures
+      .into_iter()
+      .map(|future| (Arc::new(Flag(AtomicBool::new(true))), future))
+      .collect();
+
+    while !tasks.is_empty() {
+      let mut progressed = false;
+      tasks.retain_mut(|(flag, future)| {
+        if !flag.0.swap(false, Ordering::SeqCst) {
+          return true;
+        }
+        progressed = true;
+        let waker = Waker::from(flag.clone());
+        future.as_mut().poll(&mut Context::from_waker(&waker)).is_pending()
+      });
+      assert!(progressed, "deadlock: every task is pending and none was woken");
+    }
+  }
+
+  #[test]
+  fn basic() {
+    let (tx, rx) = bounded(4);
+    block_on(async {
+      tx.send(1).await.unwrap();
+      tx.send(2).await.unwrap();
+      assert_eq!(rx.recv().await, Ok(1));
+      assert_eq!(rx.recv().await, Ok(2));
+    });
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    drop(tx);
+    assert_eq!(block_on(rx.recv()), Err(RecvError));
+  }
+
+  #[test]
+  fn try_send_full() {
+    let (tx, rx) = bounded(1);
+    assert_eq!(tx.try_send(1), Ok(()));
+    assert_eq!(tx.try_send(2), Err(TrySendError::Full(2)));
+    assert_eq!(rx.len(), 1);
+    assert_eq!(rx.capacity(), 1);
+    drop(rx);
+    assert_eq!(tx.try_send(3), Err(TrySendError::Disconnected(3)));
+  }
+
+  #[test]
+  fn back_pressure() {
+    let (tx, rx) = bounded(2);
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    let producer = {
+      let log = log.clone();
+      Box::pin(async move {
+        for i in 0..10 {
+          tx.send(i).await.unwrap();
+          log.borrow_mut().push(("sent", i));
+        }
+      })
+    };
+    let consumer = {
+      let log = log.clone();
+      Box::pin(async move {
+        while let Ok(i) = rx.recv().await {
+          assert!(rx.len() < 2);
+          log.borrow_mut().push(("recv", i));
+        }
+      })
+    };
+    run(vec![producer, consumer]);
+
+    // 生产者领先消费者的距离永远不会超过容量
+    let mut in_flight = 0i32;
+    for (event, _) in log.borrow().iter() {
+      in_flight += if *event == "sent" { 1 } else { -1 };
+      assert!((0..=2).contains(&in_flight));
+    }
+    let received: Vec<_> = log.borrow().iter().filter(|(e, _)| *e == "recv").map(|&(_, i)| i).collect();
+    assert_eq!(received, (0..10).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn mpmc() {
+    let (tx, rx) = bounded(3);
+    let mut futures: Vec<Pin<Box<dyn Future<Output = ()>>>> = Vec::new();
+    for id in 0..3 {
+      let tx = tx.clone();
+      futures.push(Box::pin(async move {
+        for i in 0..20 {
+          tx.send(id * 100 + i).await.unwrap();
+        }
+      }));
+    }
+    drop(tx);
+
+    let received = Rc::new(RefCell::new(Vec::new()));
+    for _ in 0..2 {
+      let rx = rx.clone();
+      let received = received.clone();
+      futures.push(Box::pin(async move {
+        while let Ok(i) = rx.recv().await {
+          received.borrow_mut().push(i);
+        }
+      }));
+    }
+    drop(rx);
+    run(futures);
+
+    let mut received = received.take();
+    received.sort();
+    let mut expected: Vec<_> = (0..3).flat_map(|id| (0..20).map(move |i| id * 100 + i)).collect();
+    expected.sort();
+    assert_eq!(received, expected);
+  }
+
+  #[test]
+  fn receiver_drop_wakes_blocked_sender() {
+    let (tx, rx) = bounded(1);
+    tx.try_send(1).unwrap();
+    let sender = Box::pin(async move {
+      assert_eq!(tx.send(2).await, Err(SendError(2)));
+    });
+    let closer = Box::pin(async move {
+      drop(rx);
+    });
+    run(vec![sender, closer]);
+  }
+
+  #[test]
+  fn threads() {
+    let (tx, rx) = bounded(2);
+    let handles: Vec<_> = (0..4)
+      .map(|id| {
+        let tx = tx.clone();
+        thread::spawn(move || {
+          block_on(async {
+            for i in 0..50 {
+              tx.send(id * 50 + i).await.unwrap();
+            }
+          })
+        })
+      })
+      .collect();
+    drop(tx);
+
+    let mut received = Vec::new();
+    while let Ok(i) = block_on(rx.recv()) {
+      received.push(i);
+    }
+    for handle in handles {
+      handle.join().unwrap();
+    }
+    received.sort();
+    assert_eq!(received, (0..200).collect::<Vec<_>>());
+  }
+
+  fn recv_waiting<T>(rx: &Receiver<T>) -> usize {
+    rx.shared.state.lock().unwrap().recv_waiters.waiting.len()
+  }
+
+  fn flag_waker() -> (Arc<Flag>, Waker) {
+    let flag = Arc::new(Flag(AtomicBool::new(false)));
+    (flag.clone(), Waker::from(flag))
+  }
+
+  #[test]
+  fn repoll_keeps_one_waker() {
+    let (tx, rx) = bounded::<i32>(1);
+    let (_, waker) = flag_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    // 反复 poll 同一个 future 只占一个位置，换了 waker 也一样
+    let mut recv = rx.recv();
+    for _ in 0..100 {
+      assert!(Pin::new(&mut recv).poll(&mut cx).is_pending());
+    }
+    let (_, other) = flag_waker();
+    assert!(Pin::new(&mut recv).poll(&mut Context::from_waker(&other)).is_pending());
+    assert_eq!(recv_waiting(&rx), 1);
+
+    // 取消的 future 把位置还回去
+    drop(recv);
+    assert_eq!(recv_waiting(&rx), 0);
+
+    tx.try_send(1).unwrap();
+    let mut send = tx.send(2);
+    for _ in 0..100 {
+      assert!(Pin::new(&mut send).poll(&mut cx).is_pending());
+    }
+    assert_eq!(tx.shared.state.lock().unwrap().send_waiters.waiting.len(), 1);
+    drop(send);
+    assert_eq!(tx.shared.state.lock().unwrap().send_waiters.waiting.len(), 0);
+  }
+
+  #[test]
+  fn wakes_one_and_passes_on_cancelled_notification() {
+    let (tx, rx) = bounded(4);
+    let (flag_a, waker_a) = flag_waker();
+    let (flag_b, waker_b) = flag_waker();
+    let mut a = rx.recv();
+    let mut b = rx.recv();
+    assert!(Pin::new(&mut a).poll(&mut Context::from_waker(&waker_a)).is_pending());
+    assert!(Pin::new(&mut b).poll(&mut Context::from_waker(&waker_b)).is_pending());
+
+    // 一个元素只唤醒排在前面的 a
+    tx.try_send(1).unwrap();
+    assert!(flag_a.0.load(Ordering::SeqCst));
+    assert!(!flag_b.0.load(Ordering::SeqCst));
+
+    // a 没有取走元素就被取消了，通知转给 b
+    drop(a);
+    assert!(flag_b.0.load(Ordering::SeqCst));
+    assert_eq!(Pin::new(&mut b).poll(&mut Context::from_waker(&waker_b)), Poll::Ready(Ok(1)));
+    drop(b);
+    assert_eq!(recv_waiting(&rx), 0);
+  }
+
+  #[test]
+  fn stream_and_sink() {
+    use std::future::poll_fn;
+
+    let (tx, rx) = bounded(2);
+    let mut sink = tx.into_sink();
+    let mut stream = rx.into_stream();
+
+    let producer = Box::pin(async move {
+      for i in 0..10 {
+        poll_fn(|cx| Pin::new(&mut sink).poll_ready(cx)).await.unwrap();
+        Pin::new(&mut sink).start_send(i).unwrap();
+      }
+      poll_fn(|cx| Pin::new(&mut sink).poll_close(cx)).await.unwrap();
+    });
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let consumer = {
+      let received = received.clone();
+      Box::pin(async move {
+        while let Some(i) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+          received.borrow_mut().push(i);
+        }
+      })
+    };
+    run(vec![producer, consumer]);
+    assert_eq!(*received.borrow(), (0..10).collect::<Vec<_>>());
+
+    // 接收端断开之后 sink 报错
+    let (tx, rx) = bounded::<i32>(1);
+    let mut sink = tx.into_sink();
+    drop(rx);
+    assert_eq!(block_on(poll_fn(|cx| Pin::new(&mut sink).poll_ready(cx))), Err(SendError(())));
+  }
+
+  // close 之后 sink 还活着，Stream 也要在最后一个发送端关闭之后结束
+  #[test]
+  fn stream_ends_after_sink_close() {
+    use std::future::poll_fn;
+
+    let (tx, rx) = bounded(4);
+    let other = tx.clone();
+    let mut sink = tx.into_sink();
+    let mut stream = rx.into_stream();
+    let (flag, waker) = flag_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    block_on(async {
+      poll_fn(|cx| Pin::new(&mut sink).poll_ready(cx)).await.unwrap();
+      Pin::new(&mut sink).start_send(1).unwrap();
+      poll_fn(|cx| Pin::new(&mut sink).poll_close(cx)).await.unwrap();
+    });
+    assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(Some(1)));
+
+    // 还有别的发送端，Stream 继续等
+    assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Pending);
+    drop(other);
+    assert!(flag.0.load(Ordering::SeqCst));
+    assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(None));
+
+    // 关闭之后不能再发送，重复 close 没有影响
+    assert_eq!(Pin::new(&mut sink).poll_ready(&mut cx), Poll::Ready(Err(SendError(()))));
+    assert_eq!(Pin::new(&mut sink).start_send(2), Err(SendError(())));
+    assert_eq!(Pin::new(&mut sink).poll_close(&mut cx), Poll::Ready(Ok(())));
+  }
+}
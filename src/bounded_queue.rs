@@ -0,0 +1,293 @@
+// 在 unsafe_deque_ok 的队列之上加一个容量上限，用来做背压（back-pressure）
+// 队列满时的行为由 Overflow 决定
+
+use std::ops::{Index, IndexMut};
+use std::sync::{Condvar, Mutex};
+
+use crate::unsafe_deque_ok::unsafe_deque_ok::List;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+  // 拒绝新元素，把它原样还给调用者
+  Reject,
+  // 丢掉最旧的元素给新元素腾位置，也就是环形缓冲区的语义
+  Overwrite,
+  // 等到有空位为止，只有 SyncBoundedQueue 才能真的阻塞
+  Block,
+}
+
+pub struct BoundedQueue<T> {
+  list: List<T>,
+  capacity: usize,
+  policy: Overflow,
+}
+
+impl<T> BoundedQueue<T> {
+  pub fn new(capacity: usize) -> Self {
+    Self::with_policy(capacity, Overflow::Reject)
+  }
+
+  pub fn with_policy(capacity: usize, policy: Overflow) -> Self {
+    assert!(capacity > 0, "BoundedQueue capacity must be greater than zero");
+    BoundedQueue { list: List::new(), capacity, policy }
+  }
+
+  // Ok(None): 直接放入
+  // Ok(Some(oldest)): 放入了，但挤掉了最旧的元素
+  // Err(elem): 队列已满被拒绝
+  // 单线程下没有别人能腾出空位，所以 Block 退化成 Reject
+  pub fn push(&mut self, elem: T) -> Result<Option<T>, T> {
+    if !self.is_full() {
+      self.list.push(elem);
+      return Ok(None);
+    }
+
+    match self.policy {
+      Overflow::Reject | Overflow::Block => Err(elem),
+      Overflow::Overwrite => {
+        let oldest = self.list.pop();
+        self.list.push(elem);
+        Ok(oldest)
+      }
+    }
+  }
+
+  pub fn pop(&mut self) -> Option<T> {
+    self.list.pop()
+  }
+
+  pub fn peek(&self) -> Option<&T> {
+    self.list.peek()
+  }
+
+  pub fn peek_mut(&mut self) -> Option<&mut T> {
+    self.list.peek_mut()
+  }
+
+  // 下标从队首开始数。只能读写，插入和删除仍然只在两端进行
+  pub fn get(&self, index: usize) -> Option<&T> {
+    self.list.get(index)
+  }
+
+  pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+    self.list.get_mut(index)
+  }
+
+  pub fn len(&self) -> usize {
+    self.list.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.list.is_empty()
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  pub fn is_full(&self) -> bool {
+    self.len() == self.capacity
+  }
+
+  pub fn remaining(&self) -> usize {
+    self.capacity - self.len()
+  }
+
+  pub fn policy(&self) -> Overflow {
+    self.policy
+  }
+}
+
+impl<T> Index<usize> for BoundedQueue<T> {
+  type Output = T;
+
+  fn index(&self, index: usize) -> &T {
+    &self.list[index]
+  }
+}
+
+impl<T> IndexMut<usize> for BoundedQueue<T> {
+  fn index_mut(&mut self, index: usize) -> &mut T {
+    &mut self.list[index]
+  }
+}
+
+// 线程安全的版本，Mutex 保护队列本身，两个 Condvar 分别等待 "不满" 和 "不空"
+pub struct SyncBoundedQueue<T> {
+  queue: Mutex<BoundedQueue<T>>,
+  not_full: Condvar,
+  not_empty: Condvar,
+}
+
+impl<T> SyncBoundedQueue<T> {
+  pub fn new(capacity: usize) -> Self {
+    Self::with_policy(capacity, Overflow::Block)
+  }
+
+  pub fn with_policy(capacity: usize, policy: Overflow) -> Self {
+    SyncBoundedQueue {
+      queue: Mutex::new(BoundedQueue::with_policy(capacity, policy)),
+      not_full: Condvar::new(),
+      not_empty: Condvar::new(),
+    }
+  }
+
+  // 返回值和 BoundedQueue::push 一致，Block 策略下只会返回 Ok(None)
+  pub fn push(&self, elem: T) -> Result<Option<T>, T> {
+    let mut queue = self.queue.lock().unwrap();
+    if queue.policy == Overflow::Block {
+      queue = self.not_full.wait_while(queue, |queue| queue.is_full()).unwrap();
+    }
+    let result = queue.push(elem);
+    if result.is_ok() {
+      self.not_empty.notify_one();
+    }
+    result
+  }
+
+  pub fn pop(&self) -> Option<T> {
+    let elem = self.queue.lock().unwrap().pop();
+    if elem.is_some() {
+      self.not_full.notify_one();
+    }
+    elem
+  }
+
+  // 队列为空时阻塞，直到有元素可取
+  pub fn pop_wait(&self) -> T {
+    let queue = self.queue.lock().unwrap();
+    let mut queue = self.not_empty.wait_while(queue, |queue| queue.is_empty()).unwrap();
+    let elem = queue.pop().unwrap();
+    self.not_full.notify_one();
+    elem
+  }
+
+  pub fn len(&self) -> usize {
+    self.queue.lock().unwrap().len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.queue.lock().unwrap().is_empty()
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.queue.lock().unwrap().capacity()
+  }
+
+  pub fn is_full(&self) -> bool {
+    self.queue.lock().unwrap().is_full()
+  }
+
+  pub fn remaining(&self) -> usize {
+    self.queue.lock().unwrap().remaining()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Arc;
+  use std::thread;
+
+  #[test]
+  fn reject() {
+    let mut queue = BoundedQueue::new(2);
+    assert_eq!(queue.capacity(), 2);
+    assert_eq!(queue.remaining(), 2);
+
+    assert_eq!(queue.push(1), Ok(None));
+    assert_eq!(queue.push(2), Ok(None));
+    assert!(queue.is_full());
+    assert_eq!(queue.remaining(), 0);
+    assert_eq!(queue.push(3), Err(3));
+    assert_eq!(queue.len(), 2);
+
+    assert_eq!(queue.pop(), Some(1));
+    assert_eq!(queue.remaining(), 1);
+    assert_eq!(queue.push(3), Ok(None));
+    assert_eq!(queue.pop(), Some(2));
+    assert_eq!(queue.pop(), Some(3));
+    assert_eq!(queue.pop(), None);
+    assert!(queue.is_empty());
+  }
+
+  #[test]
+  fn index() {
+    let mut queue = BoundedQueue::with_policy(3, Overflow::Overwrite);
+    for i in 0..5 {
+      queue.push(i).unwrap();
+    }
+    assert_eq!((queue.get(0), queue.get(2), queue.get(3)), (Some(&2), Some(&4), None));
+    *queue.get_mut(1).unwrap() += 10;
+    queue[2] *= 2;
+    assert_eq!((queue[0], queue[1], queue[2]), (2, 13, 8));
+  }
+
+  #[test]
+  fn overwrite() {
+    let mut queue = BoundedQueue::with_policy(3, Overflow::Overwrite);
+    for i in 0..3 {
+      assert_eq!(queue.push(i), Ok(None));
+    }
+    assert_eq!(queue.push(3), Ok(Some(0)));
+    assert_eq!(queue.push(4), Ok(Some(1)));
+    assert_eq!(queue.len(), 3);
+    assert_eq!(queue.peek(), Some(&2));
+
+    assert_eq!(queue.pop(), Some(2));
+    assert_eq!(queue.pop(), Some(3));
+    assert_eq!(queue.pop(), Some(4));
+    assert_eq!(queue.pop(), None);
+  }
+
+  #[test]
+  fn block_without_threads_rejects() {
+    let mut queue = BoundedQueue::with_policy(1, Overflow::Block);
+    assert_eq!(queue.push(1), Ok(None));
+    assert_eq!(queue.push(2), Err(2));
+  }
+
+  #[test]
+  #[should_panic]
+  fn zero_capacity() {
+    BoundedQueue::<i32>::new(0);
+  }
+
+  #[test]
+  fn sync_block() {
+    let queue = Arc::new(SyncBoundedQueue::new(2));
+
+    let producer = {
+      let queue = queue.clone();
+      thread::spawn(move || {
+        for i in 0..100 {
+          assert_eq!(queue.push(i), Ok(None));
+          assert!(queue.len() <= 2);
+        }
+      })
+    };
+
+    for i in 0..100 {
+      assert_eq!(queue.pop_wait(), i);
+    }
+    producer.join().unwrap();
+    assert!(queue.is_empty());
+    assert_eq!(queue.remaining(), 2);
+  }
+
+  #[test]
+  fn sync_reject_and_overwrite() {
+    let queue = SyncBoundedQueue::with_policy(1, Overflow::Reject);
+    assert_eq!(queue.push(1), Ok(None));
+    assert!(queue.is_full());
+    assert_eq!(queue.push(2), Err(2));
+    assert_eq!(queue.pop(), Some(1));
+    assert_eq!(queue.pop(), None);
+
+    let queue = SyncBoundedQueue::with_policy(1, Overflow::Overwrite);
+    assert_eq!(queue.push(1), Ok(None));
+    assert_eq!(queue.push(2), Ok(Some(1)));
+    assert_eq!(queue.capacity(), 1);
+    assert_eq!(queue.pop(), Some(2));
+  }
+}
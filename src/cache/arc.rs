@@ -0,0 +1,355 @@
+// ARC（Adaptive Replacement Cache，Megiddo & Modha）
+// T1: 只被访问过一次的条目，T2: 至少访问过两次的条目
+// B1、B2: 分别从 T1、T2 淘汰出去的 key（幽灵条目，只有 key 没有值）
+// 命中 B1 说明 T1 太小，命中 B2 说明 T2 太小，p 是根据这些信号自适应调整的 T1 目标大小
+// 四个链表都复用 deque 的节点，表头是最近使用的，条目在链表间移动时节点本身被复用
+
+use std::cell::{Ref, RefCell};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use super::{Borrow, Cache};
+use crate::deque::{List, Node};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Which {
+  T1,
+  T2,
+  B1,
+  B2,
+}
+
+struct Entry<K, V> {
+  key: K,
+  // 在 B1、B2 中时为 None
+  value: Option<V>,
+  which: Which,
+}
+
+type EntryRef<K, V> = Rc<RefCell<Node<Entry<K, V>>>>;
+
+struct Lists<K, V> {
+  t1: List<Entry<K, V>>,
+  t2: List<Entry<K, V>>,
+  b1: List<Entry<K, V>>,
+  b2: List<Entry<K, V>>,
+}
+
+pub struct ArcCache<K, V> {
+  // 和 LruCache 一样，map 必须先于链表释放
+  map: HashMap<K, EntryRef<K, V>>,
+  lists: Lists<K, V>,
+  capacity: usize,
+  // T1 的目标大小
+  p: usize,
+}
+
+impl<K, V> Lists<K, V> {
+  fn list(&mut self, which: Which) -> &mut List<Entry<K, V>> {
+    match which {
+      Which::T1 => &mut self.t1,
+      Which::T2 => &mut self.t2,
+      Which::B1 => &mut self.b1,
+      Which::B2 => &mut self.b2,
+    }
+  }
+
+  fn len(&self, which: Which) -> usize {
+    match which {
+      Which::T1 => self.t1.len(),
+      Which::T2 => self.t2.len(),
+      Which::B1 => self.b1.len(),
+      Which::B2 => self.b2.len(),
+    }
+  }
+
+  fn unlink(&mut self, node: &EntryRef<K, V>) {
+    let which = RefCell::borrow(node).elem().which;
+    self.list(which).unlink_node(node);
+  }
+
+  fn push_front(&mut self, which: Which, node: EntryRef<K, V>) {
+    node.borrow_mut().elem_mut().which = which;
+    self.list(which).push_front_node(node);
+  }
+
+  fn back(&mut self, which: Which) -> Option<EntryRef<K, V>> {
+    self.list(which).back_node().cloned()
+  }
+}
+
+impl<K: Hash + Eq + Clone, V> ArcCache<K, V> {
+  pub fn new(capacity: usize) -> Self {
+    ArcCache {
+      map: HashMap::new(),
+      lists: Lists { t1: List::new(), t2: List::new(), b1: List::new(), b2: List::new() },
+      capacity,
+      p: 0,
+    }
+  }
+
+  // 命中 T1 或 T2 时移到 T2 的表头；命中幽灵条目算作未命中，调整留到随后的 put
+  pub fn get<Q>(&mut self, key: &Q) -> Option<Ref<'_, V>>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let node = self.map.get(key)?;
+    match RefCell::borrow(node).elem().which {
+      Which::T1 | Which::T2 => {},
+      Which::B1 | Which::B2 => return None,
+    }
+    self.lists.unlink(node);
+    self.lists.push_front(Which::T2, node.clone());
+    Some(Ref::map(RefCell::borrow(node), |node| node.elem().value.as_ref().unwrap()))
+  }
+
+  pub fn peek<Q>(&self, key: &Q) -> Option<Ref<'_, V>>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let node = self.map.get(key)?;
+    Ref::filter_map(RefCell::borrow(node), |node| node.elem().value.as_ref()).ok()
+  }
+
+  pub fn contains_key<Q>(&self, key: &Q) -> bool
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self.map.get(key).is_some_and(|node| RefCell::borrow(node).elem().value.is_some())
+  }
+
+  // 返回被替换掉的旧值或者从缓存中淘汰的条目
+  pub fn put(&mut self, key: K, value: V) -> Vec<(K, V)> {
+    if self.capacity == 0 {
+      return vec![(key, value)];
+    }
+    let mut evicted = Vec::new();
+    let c = self.capacity;
+
+    if let Some(node) = self.map.get(&key).cloned() {
+      let which = RefCell::borrow(&node).elem().which;
+      match which {
+        // Case I: 缓存命中
+        Which::T1 | Which::T2 => {
+          let old = node.borrow_mut().elem_mut().value.replace(value).unwrap();
+          evicted.push((key, old));
+        },
+        // Case II: 命中 B1，增大 T1 的目标大小
+        Which::B1 => {
+          let delta = (self.lists.len(Which::B2) / self.lists.len(Which::B1)).max(1);
+          self.p = (self.p + delta).min(c);
+          self.replace(false, &mut evicted);
+          node.borrow_mut().elem_mut().value = Some(value);
+        },
+        // Case III: 命中 B2，减小 T1 的目标大小
+        Which::B2 => {
+          let delta = (self.lists.len(Which::B1) / self.lists.len(Which::B2)).max(1);
+          self.p = self.p.saturating_sub(delta);
+          self.replace(true, &mut evicted);
+          node.borrow_mut().elem_mut().value = Some(value);
+        },
+      }
+      self.lists.unlink(&node);
+      self.lists.push_front(Which::T2, node);
+      return evicted;
+    }
+
+    // Case IV: 完全未命中
+    let l1 = self.lists.len(Which::T1) + self.lists.len(Which::B1);
+    let total = l1 + self.lists.len(Which::T2) + self.lists.len(Which::B2);
+    if l1 == c {
+      if self.lists.len(Which::T1) < c {
+        self.drop_ghost(Which::B1);
+        self.replace(false, &mut evicted);
+      } else {
+        // B1 为空，直接丢掉 T1 最旧的条目，不留幽灵
+        let node = self.lists.back(Which::T1).unwrap();
+        evicted.extend(self.remove_node(node));
+      }
+    } else if total >= c {
+      if total == 2 * c {
+        self.drop_ghost(Which::B2);
+      }
+      self.replace(false, &mut evicted);
+    }
+
+    let node = Node::new(Entry { key: key.clone(), value: Some(value), which: Which::T1 });
+    self.map.insert(key, node.clone());
+    self.lists.push_front(Which::T1, node);
+    evicted
+  }
+
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let node = self.map.get(key)?.clone();
+    self.remove_node(node).map(|(_, value)| value)
+  }
+
+  // REPLACE：把 T1 或 T2 中最旧的条目降级成幽灵条目，它的值被淘汰
+  // 缓存没满时不需要淘汰
+  fn replace(&mut self, in_b2: bool, evicted: &mut Vec<(K, V)>) {
+    let cached = self.lists.len(Which::T1) + self.lists.len(Which::T2);
+    if cached < self.capacity {
+      return;
+    }
+    let t1 = self.lists.len(Which::T1);
+    let (from, to) = if t1 >= 1 && ((in_b2 && t1 == self.p) || t1 > self.p) {
+      (Which::T1, Which::B1)
+    } else {
+      (Which::T2, Which::B2)
+    };
+    if let Some(node) = self.lists.back(from) {
+      self.lists.unlink(&node);
+      let (key, value) = {
+        let mut inner = node.borrow_mut();
+        let entry = inner.elem_mut();
+        (entry.key.clone(), entry.value.take().unwrap())
+      };
+      evicted.push((key, value));
+      self.lists.push_front(to, node);
+    }
+  }
+
+  fn drop_ghost(&mut self, which: Which) {
+    if let Some(node) = self.lists.back(which) {
+      self.remove_node(node);
+    }
+  }
+
+  // 从 map 和所在的链表中彻底删除，返回值只在条目还有值时才有
+  fn remove_node(&mut self, node: EntryRef<K, V>) -> Option<(K, V)> {
+    self.lists.unlink(&node);
+    self.map.remove(&RefCell::borrow(&node).elem().key);
+    let entry = Rc::try_unwrap(node).ok().unwrap().into_inner().into_elem();
+    entry.value.map(|value| (entry.key, value))
+  }
+}
+
+impl<K, V> ArcCache<K, V> {
+  pub fn len(&self) -> usize {
+    self.lists.len(Which::T1) + self.lists.len(Which::T2)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> for ArcCache<K, V> {
+  fn get(&mut self, key: &K) -> Option<Ref<'_, V>> {
+    ArcCache::get(self, key)
+  }
+
+  fn peek(&self, key: &K) -> Option<Ref<'_, V>> {
+    ArcCache::peek(self, key)
+  }
+
+  fn put(&mut self, key: K, value: V) -> Vec<(K, V)> {
+    ArcCache::put(self, key, value)
+  }
+
+  fn remove(&mut self, key: &K) -> Option<V> {
+    ArcCache::remove(self, key)
+  }
+
+  fn contains_key(&self, key: &K) -> bool {
+    ArcCache::contains_key(self, key)
+  }
+
+  fn len(&self) -> usize {
+    ArcCache::len(self)
+  }
+
+  fn capacity(&self) -> usize {
+    ArcCache::capacity(self)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lens<K, V>(cache: &ArcCache<K, V>) -> [usize; 4] {
+    [Which::T1, Which::T2, Which::B1, Which::B2].map(|which| cache.lists.len(which))
+  }
+
+  #[test]
+  fn basic() {
+    let mut cache = ArcCache::new(2);
+    assert!(cache.put(1, "a").is_empty());
+    assert!(cache.put(2, "b").is_empty());
+    assert_eq!(*cache.get(&1).unwrap(), "a");
+    assert_eq!(lens(&cache), [1, 1, 0, 0]);
+
+    // T1 = [2]，T2 = [1]，淘汰 T1 中的 2 并留下幽灵
+    assert_eq!(cache.put(3, "c"), vec![(2, "b")]);
+    assert_eq!(lens(&cache), [1, 1, 1, 0]);
+    assert!(cache.get(&2).is_none());
+    assert!(!cache.contains_key(&2));
+    assert_eq!(cache.len(), 2);
+  }
+
+  #[test]
+  fn ghost_hit_adapts() {
+    let mut cache = ArcCache::new(2);
+    cache.put(1, 1);
+    cache.put(2, 2);
+    cache.get(&1);
+    // T1 = [2]，T2 = [1]，2 被降级到 B1
+    assert_eq!(cache.put(3, 3), vec![(2, 2)]);
+    assert_eq!(lens(&cache), [1, 1, 1, 0]);
+    assert_eq!(cache.p, 0);
+
+    // 命中 B1：p 变大，T1 没有超过 p，于是从 T2 淘汰
+    assert_eq!(cache.put(2, 20), vec![(1, 1)]);
+    assert_eq!(cache.p, 1);
+    assert_eq!(lens(&cache), [1, 1, 0, 1]);
+    assert_eq!(*cache.peek(&2).unwrap(), 20);
+
+    // 命中 B2：p 变小，T1 超过了 p，于是从 T1 淘汰
+    assert_eq!(cache.put(1, 10), vec![(3, 3)]);
+    assert_eq!(cache.p, 0);
+    assert_eq!(lens(&cache), [0, 2, 1, 0]);
+    assert_eq!(cache.len(), 2);
+  }
+
+  #[test]
+  fn ghost_lists_are_bounded() {
+    let mut cache = ArcCache::new(4);
+    for i in 0..1000 {
+      cache.put(i % 37, i);
+      if i % 3 == 0 {
+        cache.get(&(i % 11));
+      }
+      let [t1, t2, b1, b2] = lens(&cache);
+      assert!(t1 + t2 <= 4);
+      assert!(t1 + b1 <= 4);
+      assert!(t1 + t2 + b1 + b2 <= 8);
+      assert_eq!(cache.map.len(), t1 + t2 + b1 + b2);
+    }
+  }
+
+  #[test]
+  fn remove() {
+    let mut cache = ArcCache::new(2);
+    cache.put(1, 1);
+    cache.put(2, 2);
+    cache.put(3, 3);
+    assert_eq!(cache.remove(&3), Some(3));
+    // 幽灵条目没有值
+    assert_eq!(cache.remove(&1), None);
+    assert_eq!(cache.remove(&1), None);
+    assert_eq!(cache.len(), 1);
+  }
+}
@@ -0,0 +1,282 @@
+// O(1) 的 LFU 缓存
+// 外层是按访问次数递增排列的频率桶链表，每个桶里又是一条条目链表（表头是最近访问的）
+// 访问一个条目时把它移到下一个频率的桶里，淘汰时从频率最低的桶的表尾拿
+// 两层链表都复用 deque 的节点
+
+use std::cell::{Ref, RefCell};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::{Rc, Weak};
+
+use super::{Borrow, Cache};
+use crate::deque::{List, Node};
+
+struct Entry<K, V> {
+  key: K,
+  value: V,
+  // 条目所在的频率桶，桶由外层链表持有
+  bucket: Weak<RefCell<Node<Bucket<K, V>>>>,
+}
+
+struct Bucket<K, V> {
+  freq: usize,
+  entries: List<Entry<K, V>>,
+}
+
+type EntryRef<K, V> = Rc<RefCell<Node<Entry<K, V>>>>;
+type BucketRef<K, V> = Rc<RefCell<Node<Bucket<K, V>>>>;
+
+pub struct LfuCache<K, V> {
+  // 和 LruCache 一样，map 必须先于链表释放
+  map: HashMap<K, EntryRef<K, V>>,
+  buckets: List<Bucket<K, V>>,
+  capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> LfuCache<K, V> {
+  pub fn new(capacity: usize) -> Self {
+    LfuCache { map: HashMap::new(), buckets: List::new(), capacity }
+  }
+
+  pub fn get<Q>(&mut self, key: &Q) -> Option<Ref<'_, V>>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let node = self.map.get(key)?;
+    Self::touch(&mut self.buckets, node);
+    Some(Ref::map(RefCell::borrow(node), |node| &node.elem().value))
+  }
+
+  // 不增加访问次数
+  pub fn peek<Q>(&self, key: &Q) -> Option<Ref<'_, V>>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self.map.get(key).map(|node| Ref::map(RefCell::borrow(node), |node| &node.elem().value))
+  }
+
+  pub fn contains_key<Q>(&self, key: &Q) -> bool
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self.map.contains_key(key)
+  }
+
+  // 访问次数为 0 时不能被计数，所以 key 已存在时视为一次访问
+  // 返回被替换掉的旧值或者被淘汰的条目
+  pub fn put(&mut self, key: K, value: V) -> Vec<(K, V)> {
+    if let Some(node) = self.map.get(&key) {
+      let old = std::mem::replace(&mut node.borrow_mut().elem_mut().value, value);
+      Self::touch(&mut self.buckets, node);
+      return vec![(key, old)];
+    }
+    if self.capacity == 0 {
+      return vec![(key, value)];
+    }
+
+    let mut evicted = Vec::new();
+    if self.map.len() == self.capacity {
+      evicted.extend(self.pop_lfu());
+    }
+
+    let bucket = match self.buckets.front_node() {
+      Some(front) if RefCell::borrow(front).elem().freq == 1 => front.clone(),
+      _ => {
+        let bucket = Node::new(Bucket { freq: 1, entries: List::new() });
+        self.buckets.push_front_node(bucket.clone());
+        bucket
+      }
+    };
+    let node = Node::new(Entry { key: key.clone(), value, bucket: Rc::downgrade(&bucket) });
+    bucket.borrow_mut().elem_mut().entries.push_front_node(node.clone());
+    self.map.insert(key, node);
+    evicted
+  }
+
+  // 淘汰访问次数最少的条目，次数相同时淘汰最久没访问的
+  pub fn pop_lfu(&mut self) -> Option<(K, V)> {
+    let node = {
+      let bucket = RefCell::borrow(self.buckets.front_node()?);
+      let victim = RefCell::borrow(bucket.elem().entries.back_node()?);
+      self.map.remove(&victim.elem().key)?
+    };
+    Self::unlink(&mut self.buckets, &node);
+    let entry = Rc::try_unwrap(node).ok().unwrap().into_inner().into_elem();
+    Some((entry.key, entry.value))
+  }
+
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let node = self.map.remove(key)?;
+    Self::unlink(&mut self.buckets, &node);
+    Some(Rc::try_unwrap(node).ok().unwrap().into_inner().into_elem().value)
+  }
+
+  // 当前的访问次数，peek 不计入
+  pub fn frequency<Q>(&self, key: &Q) -> Option<usize>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let node = self.map.get(key)?;
+    let bucket = RefCell::borrow(node).elem().bucket.upgrade().unwrap();
+    let freq = RefCell::borrow(&bucket).elem().freq;
+    Some(freq)
+  }
+
+  // 把条目从 freq 的桶移到 freq + 1 的桶，需要时新建桶、删除空桶
+  fn touch(buckets: &mut List<Bucket<K, V>>, node: &EntryRef<K, V>) {
+    let bucket = RefCell::borrow(node).elem().bucket.upgrade().unwrap();
+    let freq = RefCell::borrow(&bucket).elem().freq;
+    bucket.borrow_mut().elem_mut().entries.unlink_node(node);
+
+    let next = RefCell::borrow(&bucket).next_node().cloned();
+    let target = match next {
+      Some(next) if RefCell::borrow(&next).elem().freq == freq + 1 => next,
+      _ => {
+        let target = Node::new(Bucket { freq: freq + 1, entries: List::new() });
+        buckets.insert_node_after(&bucket, target.clone());
+        target
+      }
+    };
+    target.borrow_mut().elem_mut().entries.push_front_node(node.clone());
+    node.borrow_mut().elem_mut().bucket = Rc::downgrade(&target);
+
+    Self::remove_if_empty(buckets, &bucket);
+  }
+
+  fn unlink(buckets: &mut List<Bucket<K, V>>, node: &EntryRef<K, V>) {
+    let bucket = RefCell::borrow(node).elem().bucket.upgrade().unwrap();
+    bucket.borrow_mut().elem_mut().entries.unlink_node(node);
+    Self::remove_if_empty(buckets, &bucket);
+  }
+
+  fn remove_if_empty(buckets: &mut List<Bucket<K, V>>, bucket: &BucketRef<K, V>) {
+    if RefCell::borrow(bucket).elem().entries.front_node().is_none() {
+      buckets.unlink_node(bucket);
+    }
+  }
+}
+
+impl<K, V> LfuCache<K, V> {
+  pub fn len(&self) -> usize {
+    self.map.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.map.is_empty()
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> for LfuCache<K, V> {
+  fn get(&mut self, key: &K) -> Option<Ref<'_, V>> {
+    LfuCache::get(self, key)
+  }
+
+  fn peek(&self, key: &K) -> Option<Ref<'_, V>> {
+    LfuCache::peek(self, key)
+  }
+
+  fn put(&mut self, key: K, value: V) -> Vec<(K, V)> {
+    LfuCache::put(self, key, value)
+  }
+
+  fn remove(&mut self, key: &K) -> Option<V> {
+    LfuCache::remove(self, key)
+  }
+
+  fn contains_key(&self, key: &K) -> bool {
+    LfuCache::contains_key(self, key)
+  }
+
+  fn len(&self) -> usize {
+    LfuCache::len(self)
+  }
+
+  fn capacity(&self) -> usize {
+    LfuCache::capacity(self)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn evicts_least_frequent() {
+    let mut cache = LfuCache::new(2);
+    cache.put("a", 1);
+    cache.put("b", 2);
+    cache.get("a");
+    assert_eq!(cache.frequency("a"), Some(2));
+    assert_eq!(cache.frequency("b"), Some(1));
+
+    assert_eq!(cache.put("c", 3), vec![("b", 2)]);
+    assert!(cache.get("b").is_none());
+    assert_eq!(*cache.get("a").unwrap(), 1);
+    assert_eq!(cache.frequency("a"), Some(3));
+  }
+
+  #[test]
+  fn ties_evict_least_recent() {
+    let mut cache = LfuCache::new(3);
+    cache.put(1, "a");
+    cache.put(2, "b");
+    cache.put(3, "c");
+    cache.get(&2);
+    cache.get(&1);
+    // 1 和 2 的次数都是 2，3 的次数是 1
+    assert_eq!(cache.pop_lfu(), Some((3, "c")));
+    // 1 和 2 次数相同，2 更早被访问
+    assert_eq!(cache.pop_lfu(), Some((2, "b")));
+    assert_eq!(cache.pop_lfu(), Some((1, "a")));
+    assert_eq!(cache.pop_lfu(), None);
+  }
+
+  #[test]
+  fn put_existing_counts_as_access() {
+    let mut cache = LfuCache::new(2);
+    cache.put(1, 10);
+    assert_eq!(cache.put(1, 11), vec![(1, 10)]);
+    assert_eq!(cache.frequency(&1), Some(2));
+    cache.put(2, 20);
+    assert_eq!(cache.put(3, 30), vec![(2, 20)]);
+    assert_eq!(*cache.peek(&1).unwrap(), 11);
+    assert_eq!(cache.frequency(&1), Some(2));
+  }
+
+  #[test]
+  fn remove_and_empty_buckets() {
+    let mut cache = LfuCache::new(4);
+    for i in 0..4 {
+      cache.put(i, i);
+      for _ in 0..i {
+        cache.get(&i);
+      }
+    }
+    assert_eq!(cache.remove(&2), Some(2));
+    assert_eq!(cache.remove(&2), None);
+    assert_eq!(cache.len(), 3);
+    assert_eq!(cache.pop_lfu(), Some((0, 0)));
+    assert_eq!(cache.pop_lfu(), Some((1, 1)));
+    assert_eq!(cache.pop_lfu(), Some((3, 3)));
+    assert!(cache.is_empty());
+  }
+
+  #[test]
+  fn zero_capacity() {
+    let mut cache = LfuCache::new(0);
+    assert_eq!(cache.put(1, 1), vec![(1, 1)]);
+    assert!(cache.is_empty());
+  }
+}
@@ -0,0 +1,298 @@
+// LRU 缓存：HashMap 负责 O(1) 查找，deque 的节点串成的双向链表负责记录新旧顺序
+// 表头是最近使用的，表尾是最久没用的
+// 命中时只是把节点摘下来再挂到表头，节点本身被复用，不会分配内存
+
+use std::cell::{Ref, RefCell};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use super::{Borrow, Cache};
+use crate::deque::{List, Node};
+
+struct Entry<K, V> {
+  key: K,
+  value: V,
+  // 插入时由 weigher 算出来，之后不再变化
+  weight: usize,
+}
+
+type NodeRef<K, V> = Rc<RefCell<Node<Entry<K, V>>>>;
+type Weigher<K, V> = Box<dyn Fn(&K, &V) -> usize>;
+
+pub struct LruCache<K, V> {
+  // map 和 list 各持有节点的一个 Rc，字段按声明顺序析构，map 要先释放，
+  // list 的 Drop 才能用 Rc::try_unwrap 取出元素
+  map: HashMap<K, NodeRef<K, V>>,
+  list: List<Entry<K, V>>,
+  capacity: usize,
+  weight: usize,
+  weigher: Option<Weigher<K, V>>,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+  // 最多容纳 capacity 个条目
+  pub fn new(capacity: usize) -> Self {
+    LruCache { map: HashMap::new(), list: List::new(), capacity, weight: 0, weigher: None }
+  }
+
+  // 条目的总权重不超过 capacity，权重由 weigher 计算
+  pub fn with_weigher(capacity: usize, weigher: impl Fn(&K, &V) -> usize + 'static) -> Self {
+    LruCache { weigher: Some(Box::new(weigher)), ..Self::new(capacity) }
+  }
+
+  pub fn get<Q>(&mut self, key: &Q) -> Option<Ref<'_, V>>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let node = self.map.get(key)?;
+    let is_front = self.list.front_node().is_some_and(|front| Rc::ptr_eq(front, node));
+    if !is_front {
+      self.list.unlink_node(node);
+      self.list.push_front_node(node.clone());
+    }
+    Some(Ref::map(RefCell::borrow(node), |node| &node.elem().value))
+  }
+
+  // 和 get 一样，但不改变新旧顺序
+  pub fn peek<Q>(&self, key: &Q) -> Option<Ref<'_, V>>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self.map.get(key).map(|node| Ref::map(RefCell::borrow(node), |node| &node.elem().value))
+  }
+
+  pub fn contains_key<Q>(&self, key: &Q) -> bool
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self.map.contains_key(key)
+  }
+
+  // 返回这次调用移出缓存的所有条目：同一个 key 的旧值，以及因为超出容量被淘汰的条目（从旧到新）
+  // 如果新条目自身的权重就超过了容量，它也会被立即淘汰
+  pub fn put(&mut self, key: K, value: V) -> Vec<(K, V)> {
+    let mut evicted = Vec::new();
+    if let Some(old) = self.remove_entry(&key) {
+      evicted.push(old);
+    }
+
+    let weight = self.weigh(&key, &value);
+    let node = Node::new(Entry { key: key.clone(), value, weight });
+    self.map.insert(key, node.clone());
+    self.list.push_front_node(node);
+    self.weight += weight;
+
+    self.evict_to(self.capacity, &mut evicted);
+    evicted
+  }
+
+  pub fn pop_lru(&mut self) -> Option<(K, V)> {
+    let node = self.list.back_node()?.clone();
+    self.list.unlink_node(&node);
+    self.map.remove(&RefCell::borrow(&node).elem().key);
+    let entry = Self::into_entry(node);
+    self.weight -= entry.weight;
+    Some((entry.key, entry.value))
+  }
+
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self.remove_entry(key).map(|(_, value)| value)
+  }
+
+  // 调整容量，返回因为容量变小而被淘汰的条目
+  pub fn resize(&mut self, capacity: usize) -> Vec<(K, V)> {
+    self.capacity = capacity;
+    let mut evicted = Vec::new();
+    self.evict_to(capacity, &mut evicted);
+    evicted
+  }
+
+  fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let node = self.map.remove(key)?;
+    self.list.unlink_node(&node);
+    let entry = Self::into_entry(node);
+    self.weight -= entry.weight;
+    Some((entry.key, entry.value))
+  }
+
+  fn evict_to(&mut self, capacity: usize, evicted: &mut Vec<(K, V)>) {
+    while self.weight > capacity {
+      match self.pop_lru() {
+        Some(entry) => evicted.push(entry),
+        None => break,
+      }
+    }
+  }
+
+  fn weigh(&self, key: &K, value: &V) -> usize {
+    self.weigher.as_ref().map_or(1, |weigher| weigher(key, value))
+  }
+
+  // 节点已经从 map 和 list 中摘下，此时只剩调用者手上的这一个 Rc
+  fn into_entry(node: NodeRef<K, V>) -> Entry<K, V> {
+    Rc::try_unwrap(node).ok().unwrap().into_inner().into_elem()
+  }
+}
+
+impl<K, V> LruCache<K, V> {
+  pub fn len(&self) -> usize {
+    self.map.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.map.is_empty()
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  // 当前所有条目的总权重，没有 weigher 时等于 len
+  pub fn weight(&self) -> usize {
+    self.weight
+  }
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> for LruCache<K, V> {
+  fn get(&mut self, key: &K) -> Option<Ref<'_, V>> {
+    LruCache::get(self, key)
+  }
+
+  fn peek(&self, key: &K) -> Option<Ref<'_, V>> {
+    LruCache::peek(self, key)
+  }
+
+  fn put(&mut self, key: K, value: V) -> Vec<(K, V)> {
+    LruCache::put(self, key, value)
+  }
+
+  fn remove(&mut self, key: &K) -> Option<V> {
+    LruCache::remove(self, key)
+  }
+
+  fn contains_key(&self, key: &K) -> bool {
+    LruCache::contains_key(self, key)
+  }
+
+  fn len(&self) -> usize {
+    LruCache::len(self)
+  }
+
+  fn capacity(&self) -> usize {
+    LruCache::capacity(self)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn basic() {
+    let mut cache = LruCache::new(2);
+    assert!(cache.put("a", 1).is_empty());
+    assert!(cache.put("b", 2).is_empty());
+    assert_eq!(*cache.get("a").unwrap(), 1);
+
+    // b 是最久没用的
+    assert_eq!(cache.put("c", 3), vec![("b", 2)]);
+    assert!(cache.get("b").is_none());
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.capacity(), 2);
+  }
+
+  #[test]
+  fn peek_does_not_promote() {
+    let mut cache = LruCache::new(2);
+    cache.put(1, "a");
+    cache.put(2, "b");
+    assert_eq!(*cache.peek(&1).unwrap(), "a");
+    assert_eq!(cache.put(3, "c"), vec![(1, "a")]);
+    assert!(cache.contains_key(&2));
+    assert!(!cache.contains_key(&1));
+  }
+
+  #[test]
+  fn replace_existing_key() {
+    let mut cache = LruCache::new(2);
+    cache.put(1, "a");
+    cache.put(2, "b");
+    assert_eq!(cache.put(1, "A"), vec![(1, "a")]);
+    assert_eq!(cache.len(), 2);
+    // 重新 put 会刷新 1 的位置
+    assert_eq!(cache.pop_lru(), Some((2, "b")));
+    assert_eq!(cache.pop_lru(), Some((1, "A")));
+    assert_eq!(cache.pop_lru(), None);
+    assert!(cache.is_empty());
+  }
+
+  #[test]
+  fn get_promotes() {
+    let mut cache = LruCache::new(3);
+    for i in 0..3 {
+      cache.put(i, i * 10);
+    }
+    cache.get(&0);
+    cache.get(&1);
+    assert_eq!(cache.pop_lru(), Some((2, 20)));
+    assert_eq!(cache.pop_lru(), Some((0, 0)));
+    assert_eq!(cache.remove(&1), Some(10));
+    assert_eq!(cache.remove(&1), None);
+  }
+
+  #[test]
+  fn resize() {
+    let mut cache = LruCache::new(4);
+    for i in 0..4 {
+      cache.put(i, i);
+    }
+    assert_eq!(cache.resize(2), vec![(0, 0), (1, 1)]);
+    assert_eq!(cache.len(), 2);
+    assert!(cache.resize(8).is_empty());
+    for i in 4..10 {
+      cache.put(i, i);
+    }
+    assert_eq!(cache.len(), 8);
+  }
+
+  #[test]
+  fn weigher() {
+    let mut cache = LruCache::with_weigher(10, |_: &&str, value: &String| value.len());
+    cache.put("a", "xxxx".to_string());
+    cache.put("b", "yyyy".to_string());
+    assert_eq!(cache.weight(), 8);
+
+    // 需要淘汰两个条目才能放下
+    let evicted = cache.put("c", "zzzzzzzz".to_string());
+    assert_eq!(evicted, vec![("a", "xxxx".to_string()), ("b", "yyyy".to_string())]);
+    assert_eq!(cache.weight(), 8);
+
+    // 比整个容量还大的条目放不进去
+    let evicted = cache.put("d", "w".repeat(11));
+    assert_eq!(evicted.len(), 2);
+    assert_eq!(evicted[1].0, "d");
+    assert!(cache.is_empty());
+    assert_eq!(cache.weight(), 0);
+  }
+
+  #[test]
+  fn drop_with_entries() {
+    let mut cache = LruCache::new(100);
+    for i in 0..100 {
+      cache.put(i, vec![i; 4]);
+    }
+    drop(cache);
+  }
+}
@@ -0,0 +1,139 @@
+// 几种缓存策略共用的接口，方便在同一段代码里切换策略做比较
+
+use std::cell::Ref;
+
+// 各策略的 key 查找都支持 K: Borrow<Q>。引入 Borrow 之后 Rc<RefCell<_>> 上的 .borrow() 会产生歧义，
+// 所以子模块里统一写成 RefCell::borrow(node)
+pub(crate) use std::borrow::Borrow;
+
+pub mod lru;
+pub mod lfu;
+pub mod arc;
+
+pub trait Cache<K, V> {
+  // 命中时返回值，并按各自的策略记录这次访问
+  fn get(&mut self, key: &K) -> Option<Ref<'_, V>>;
+
+  // 只读，不影响淘汰顺序
+  fn peek(&self, key: &K) -> Option<Ref<'_, V>>;
+
+  // 返回这次调用移出缓存的条目，包括同一个 key 被替换掉的旧值
+  fn put(&mut self, key: K, value: V) -> Vec<(K, V)>;
+
+  fn remove(&mut self, key: &K) -> Option<V>;
+
+  fn contains_key(&self, key: &K) -> bool;
+
+  fn len(&self) -> usize;
+
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  fn capacity(&self) -> usize;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::arc::ArcCache;
+  use super::lfu::LfuCache;
+  use super::lru::LruCache;
+
+  // 固定种子的访问序列，命中数一旦变化说明淘汰策略的行为变了
+  struct Rng(u64);
+
+  impl Rng {
+    fn next(&mut self) -> u64 {
+      self.0 ^= self.0 << 13;
+      self.0 ^= self.0 >> 7;
+      self.0 ^= self.0 << 17;
+      self.0
+    }
+
+    fn below(&mut self, n: u64) -> u32 {
+      (self.next() % n) as u32
+    }
+  }
+
+  // 80% 的访问落在 20 个热点 key 上，其余均匀分布在 1000 个 key 上
+  fn skewed_trace() -> Vec<u32> {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    (0..20_000)
+      .map(|_| if rng.below(10) < 8 { rng.below(20) } else { 20 + rng.below(1000) })
+      .collect()
+  }
+
+  // 反复访问 30 个 key 的工作集，中间穿插只访问一次的长扫描
+  fn scan_trace() -> Vec<u32> {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let mut trace = Vec::new();
+    let mut scan_key = 10_000;
+    for round in 0..200 {
+      for _ in 0..60 {
+        trace.push(rng.below(30));
+      }
+      if round % 4 == 0 {
+        for _ in 0..100 {
+          trace.push(scan_key);
+          scan_key += 1;
+        }
+      }
+    }
+    trace
+  }
+
+  // 每 500 次访问整体切换一次工作集
+  fn shifting_trace() -> Vec<u32> {
+    let mut rng = Rng(0x1234_5678_9abc_def1);
+    (0..20_000u32).map(|i| (i / 500) * 100 + rng.below(40)).collect()
+  }
+
+  fn hits<C: Cache<u32, u32>>(cache: &mut C, trace: &[u32]) -> usize {
+    let mut hits = 0;
+    for &key in trace {
+      if cache.get(&key).is_some() {
+        hits += 1;
+      } else {
+        cache.put(key, key);
+      }
+      assert!(cache.len() <= cache.capacity());
+    }
+    hits
+  }
+
+  fn run(trace: &[u32], capacity: usize) -> [usize; 3] {
+    [
+      hits(&mut LruCache::new(capacity), trace),
+      hits(&mut LfuCache::new(capacity), trace),
+      hits(&mut ArcCache::new(capacity), trace),
+    ]
+  }
+
+  #[test]
+  #[cfg_attr(miri, ignore)]
+  fn skewed_hit_ratio() {
+    let [lru, lfu, arc] = run(&skewed_trace(), 32);
+    assert_eq!([lru, lfu, arc], [14967, 16026, 16008]);
+    // 热点稳定时，频率信息比最近一次访问更有用
+    assert!(lfu > lru && arc > lru);
+  }
+
+  #[test]
+  #[cfg_attr(miri, ignore)]
+  fn scan_hit_ratio() {
+    let [lru, lfu, arc] = run(&scan_trace(), 40);
+    assert_eq!([lru, lfu, arc], [10471, 11960, 11960]);
+    // 扫描会把 LRU 的工作集冲掉，ARC 把只访问一次的条目限制在 T1 里
+    assert!(arc > lru);
+  }
+
+  #[test]
+  #[cfg_attr(miri, ignore)]
+  fn shifting_hit_ratio() {
+    let [lru, lfu, arc] = run(&shifting_trace(), 50);
+    assert_eq!([lru, lfu, arc], [18400, 1041, 18385]);
+    // 旧工作集积累的访问次数让 LFU 无法适应变化，ARC 能跟上 LRU
+    assert!(lfu < lru / 10 && arc > lru * 9 / 10);
+  }
+}
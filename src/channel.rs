@@ -0,0 +1,483 @@
+// 多生产者单消费者的阻塞 channel，底层是 unsafe_deque_ok 的链式队列
+// 所有状态放在一把 Mutex 里，Condvar 只用来等待 "有新元素或者发送端全部断开"
+
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::unsafe_deque_ok::unsafe_deque_ok::List;
+
+struct State<T> {
+  queue: List<T>,
+  senders: usize,
+  receiver_alive: bool,
+}
+
+struct Shared<T> {
+  state: Mutex<State<T>>,
+  available: Condvar,
+}
+
+pub struct Sender<T> {
+  shared: Arc<Shared<T>>,
+}
+
+// 和 std::sync::mpsc::Receiver 不同，这里的 Receiver 是 Sync 的（T: Send 时）：
+// 所有状态都在 Mutex 里，多个线程通过 &Receiver 同时 recv 也只是轮流取走不同的元素
+pub struct Receiver<T> {
+  shared: Arc<Shared<T>>,
+}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+  let shared = Arc::new(Shared {
+    state: Mutex::new(State { queue: List::new(), senders: 1, receiver_alive: true }),
+    available: Condvar::new(),
+  });
+  (Sender { shared: shared.clone() }, Receiver { shared })
+}
+
+// 接收端已经被 drop，原样把值还回去
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct SendError<T>(pub T);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RecvError;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TryRecvError {
+  Empty,
+  Disconnected,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RecvTimeoutError {
+  Timeout,
+  Disconnected,
+}
+
+// 和 std::sync::mpsc 一样，Debug 不要求 T: Debug
+impl<T> fmt::Debug for SendError<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("SendError { .. }")
+  }
+}
+
+impl<T> fmt::Display for SendError<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("sending on a closed channel")
+  }
+}
+
+impl fmt::Display for RecvError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("receiving on a closed channel")
+  }
+}
+
+impl fmt::Display for TryRecvError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TryRecvError::Empty => f.write_str("receiving on an empty channel"),
+      TryRecvError::Disconnected => f.write_str("receiving on a closed channel"),
+    }
+  }
+}
+
+impl fmt::Display for RecvTimeoutError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      RecvTimeoutError::Timeout => f.write_str("timed out waiting on channel"),
+      RecvTimeoutError::Disconnected => f.write_str("channel is empty and sending half is closed"),
+    }
+  }
+}
+
+impl<T> std::error::Error for SendError<T> {}
+impl std::error::Error for RecvError {}
+impl std::error::Error for TryRecvError {}
+impl std::error::Error for RecvTimeoutError {}
+
+impl<T> Sender<T> {
+  pub fn send(&self, elem: T) -> Result<(), SendError<T>> {
+    let mut state = self.shared.state.lock().unwrap();
+    if !state.receiver_alive {
+      return Err(SendError(elem));
+    }
+    state.queue.push(elem);
+    drop(state);
+    self.shared.available.notify_one();
+    Ok(())
+  }
+}
+
+impl<T> Clone for Sender<T> {
+  fn clone(&self) -> Self {
+    self.shared.state.lock().unwrap().senders += 1;
+    Sender { shared: self.shared.clone() }
+  }
+}
+
+impl<T> Drop for Sender<T> {
+  fn drop(&mut self) {
+    let mut state = self.shared.state.lock().unwrap();
+    state.senders -= 1;
+    let last = state.senders == 0;
+    drop(state);
+    // 最后一个发送端离开，唤醒正在等待的接收端让它看到断开
+    if last {
+      self.shared.available.notify_all();
+    }
+  }
+}
+
+impl<T> Receiver<T> {
+  pub fn recv(&self) -> Result<T, RecvError> {
+    let mut state = self.shared.state.lock().unwrap();
+    loop {
+      if let Some(elem) = state.queue.pop() {
+        return Ok(elem);
+      }
+      if state.senders == 0 {
+        return Err(RecvError);
+      }
+      state = self.shared.available.wait(state).unwrap();
+    }
+  }
+
+  pub fn try_recv(&self) -> Result<T, TryRecvError> {
+    let mut state = self.shared.state.lock().unwrap();
+    match state.queue.pop() {
+      Some(elem) => Ok(elem),
+      None if state.senders == 0 => Err(TryRecvError::Disconnected),
+      None => Err(TryRecvError::Empty),
+    }
+  }
+
+  pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+    // 超时太大（比如 Duration::MAX）时 Instant 会溢出，相当于不设超时
+    let Some(deadline) = Instant::now().checked_add(timeout) else {
+      return self.recv().map_err(|RecvError| RecvTimeoutError::Disconnected);
+    };
+    let mut state = self.shared.state.lock().unwrap();
+    loop {
+      if let Some(elem) = state.queue.pop() {
+        return Ok(elem);
+      }
+      if state.senders == 0 {
+        return Err(RecvTimeoutError::Disconnected);
+      }
+      let now = Instant::now();
+      if now >= deadline {
+        return Err(RecvTimeoutError::Timeout);
+      }
+      // 可能是虚假唤醒，所以回到循环开头重新检查，而不是看 WaitTimeoutResult
+      state = self.shared.available.wait_timeout(state, deadline - now).unwrap().0;
+    }
+  }
+
+  // 阻塞迭代，直到所有发送端断开并且队列被取空
+  pub fn iter(&self) -> Iter<'_, T> {
+    Iter { rx: self }
+  }
+
+  // 只取出当前已经在队列里的元素，不阻塞
+  pub fn try_iter(&self) -> TryIter<'_, T> {
+    TryIter { rx: self }
+  }
+}
+
+impl<T> Drop for Receiver<T> {
+  fn drop(&mut self) {
+    let mut state = self.shared.state.lock().unwrap();
+    state.receiver_alive = false;
+    // 没人会再取了，尽早释放队列里剩下的元素
+    let queue = std::mem::take(&mut state.queue);
+    drop(state);
+    drop(queue);
+  }
+}
+
+pub struct Iter<'a, T> {
+  rx: &'a Receiver<T>,
+}
+
+impl<T> Iterator for Iter<'_, T> {
+  type Item = T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.rx.recv().ok()
+  }
+}
+
+pub struct TryIter<'a, T> {
+  rx: &'a Receiver<T>,
+}
+
+impl<T> Iterator for TryIter<'_, T> {
+  type Item = T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.rx.try_recv().ok()
+  }
+}
+
+pub struct IntoIter<T> {
+  rx: Receiver<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+  type Item = T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.rx.recv().ok()
+  }
+}
+
+impl<T> IntoIterator for Receiver<T> {
+  type Item = T;
+  type IntoIter = IntoIter<T>;
+
+  fn into_iter(self) -> IntoIter<T> {
+    IntoIter { rx: self }
+  }
+}
+
+impl<'a, T> IntoIterator for &'a Receiver<T> {
+  type Item = T;
+  type IntoIter = Iter<'a, T>;
+
+  fn into_iter(self) -> Iter<'a, T> {
+    self.iter()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::thread;
+
+  #[test]
+  fn basic() {
+    let (tx, rx) = channel();
+    tx.send(1).unwrap();
+    tx.send(2).unwrap();
+    assert_eq!(rx.recv(), Ok(1));
+    assert_eq!(rx.try_recv(), Ok(2));
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+
+    drop(tx);
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(rx.recv(), Err(RecvError));
+  }
+
+  #[test]
+  fn drain_after_disconnect() {
+    let (tx, rx) = channel();
+    tx.send(1).unwrap();
+    tx.send(2).unwrap();
+    drop(tx);
+    // 断开之前发送的值仍然能取到
+    assert_eq!(rx.iter().collect::<Vec<_>>(), vec![1, 2]);
+  }
+
+  #[test]
+  fn send_after_receiver_dropped() {
+    let (tx, rx) = channel();
+    drop(rx);
+    assert_eq!(tx.send(1), Err(SendError(1)));
+  }
+
+  #[test]
+  fn recv_timeout() {
+    let (tx, rx) = channel::<i32>();
+    assert_eq!(rx.recv_timeout(Duration::from_millis(10)), Err(RecvTimeoutError::Timeout));
+
+    tx.send(1).unwrap();
+    assert_eq!(rx.recv_timeout(Duration::from_millis(10)), Ok(1));
+
+    let handle = thread::spawn(move || {
+      thread::sleep(Duration::from_millis(10));
+      tx.send(2).unwrap();
+    });
+    assert_eq!(rx.recv_timeout(Duration::from_secs(10)), Ok(2));
+    handle.join().unwrap();
+    assert_eq!(rx.recv_timeout(Duration::from_secs(10)), Err(RecvTimeoutError::Disconnected));
+  }
+
+  #[test]
+  fn recv_timeout_max() {
+    let (tx, rx) = channel();
+    tx.send(1).unwrap();
+    assert_eq!(rx.recv_timeout(Duration::MAX), Ok(1));
+
+    let handle = thread::spawn(move || {
+      thread::sleep(Duration::from_millis(10));
+      tx.send(2).unwrap();
+    });
+    assert_eq!(rx.recv_timeout(Duration::MAX), Ok(2));
+    handle.join().unwrap();
+    assert_eq!(rx.recv_timeout(Duration::MAX), Err(RecvTimeoutError::Disconnected));
+  }
+
+  #[test]
+  fn try_iter() {
+    let (tx, rx) = channel();
+    tx.send(1).unwrap();
+    tx.send(2).unwrap();
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![1, 2]);
+    // 发送端还活着，try_iter 也不会阻塞
+    assert_eq!(rx.try_iter().next(), None);
+  }
+
+  #[test]
+  fn multiple_senders() {
+    let (tx, rx) = channel();
+    let handles: Vec<_> = (0..4)
+      .map(|id| {
+        let tx = tx.clone();
+        thread::spawn(move || {
+          for i in 0..100 {
+            tx.send(id * 100 + i).unwrap();
+          }
+        })
+      })
+      .collect();
+    drop(tx);
+
+    let mut received: Vec<_> = rx.into_iter().collect();
+    for handle in handles {
+      handle.join().unwrap();
+    }
+    received.sort();
+    assert_eq!(received, (0..400).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn fifo_per_sender() {
+    let (tx, rx) = channel();
+    let tx2 = tx.clone();
+    let a = thread::spawn(move || (0..100).for_each(|i| tx.send((0, i)).unwrap()));
+    let b = thread::spawn(move || (0..100).for_each(|i| tx2.send((1, i)).unwrap()));
+
+    let mut next = [0, 0];
+    for (id, i) in &rx {
+      assert_eq!(next[id], i);
+      next[id] += 1;
+    }
+    a.join().unwrap();
+    b.join().unwrap();
+    assert_eq!(next, [100, 100]);
+  }
+
+  // 接收端阻塞等待时，最后一个发送端退出必须把它唤醒
+  #[test]
+  fn shutdown_wakes_blocked_receiver() {
+    for _ in 0..50 {
+      let (tx, rx) = channel::<i32>();
+      let senders: Vec<_> = (0..4).map(|_| tx.clone()).collect();
+      drop(tx);
+      let receiver = thread::spawn(move || rx.recv());
+      let handles: Vec<_> = senders.into_iter().map(|tx| thread::spawn(move || drop(tx))).collect();
+      for handle in handles {
+        handle.join().unwrap();
+      }
+      assert_eq!(receiver.join().unwrap(), Err(RecvError));
+    }
+  }
+
+  // 发送和接收端的关闭同时发生：每个值要么发送成功，要么在 SendError 里原样还回来；
+  // 发送成功的要么被接收，要么随队列释放，每个值恰好 drop 一次
+  #[test]
+  fn shutdown_race_drops_every_value() {
+    struct Counted(usize, Arc<Mutex<Vec<usize>>>);
+    impl Drop for Counted {
+      fn drop(&mut self) {
+        self.1.lock().unwrap().push(self.0);
+      }
+    }
+
+    for round in 0..50 {
+      let dropped = Arc::new(Mutex::new(Vec::new()));
+      let (tx, rx) = channel();
+      let handles: Vec<_> = (0..3)
+        .map(|t| {
+          let tx = tx.clone();
+          let dropped = dropped.clone();
+          thread::spawn(move || {
+            let (mut sent, mut returned) = (Vec::new(), Vec::new());
+            for i in t * 20..(t + 1) * 20 {
+              match tx.send(Counted(i, dropped.clone())) {
+                Ok(()) => sent.push(i),
+                Err(SendError(elem)) => returned.push(elem.0),
+              }
+            }
+            (sent, returned)
+          })
+        })
+        .collect();
+      drop(tx);
+
+      let receiver = thread::spawn(move || {
+        let mut received = Vec::new();
+        while received.len() < round {
+          match rx.recv() {
+            Ok(elem) => received.push(elem.0),
+            Err(RecvError) => break,
+          }
+        }
+        received
+      });
+
+      let (mut sent, mut returned) = (Vec::new(), Vec::new());
+      for handle in handles {
+        let (s, r) = handle.join().unwrap();
+        sent.extend(s);
+        returned.extend(r);
+      }
+      let received = receiver.join().unwrap();
+
+      // 发送端看到的结果把 60 个值不重不漏地分成了两份
+      let mut all: Vec<_> = sent.iter().chain(&returned).copied().collect();
+      all.sort_unstable();
+      assert_eq!(all, (0..60).collect::<Vec<_>>());
+      // 收到的一定是发送成功的
+      assert!(received.iter().all(|i| sent.contains(i)));
+      // 接收端关闭后所有值都已经释放，而且只释放了一次
+      let mut dropped = dropped.lock().unwrap().clone();
+      dropped.sort_unstable();
+      assert_eq!(dropped, (0..60).collect::<Vec<_>>());
+    }
+  }
+
+  // Receiver 是 Sync 的，几个线程可以共用一个 &Receiver 一起取
+  #[test]
+  fn shared_receiver() {
+    let (tx, rx) = channel();
+    for i in 0..100 {
+      tx.send(i).unwrap();
+    }
+    drop(tx);
+    let mut all: Vec<i32> = thread::scope(|s| {
+      let handles: Vec<_> = (0..4).map(|_| s.spawn(|| rx.iter().collect::<Vec<_>>())).collect();
+      handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+    });
+    all.sort_unstable();
+    assert_eq!(all, (0..100).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn recv_timeout_races_disconnect() {
+    for _ in 0..50 {
+      let (tx, rx) = channel::<i32>();
+      let handle = thread::spawn(move || drop(tx));
+      loop {
+        match rx.recv_timeout(Duration::from_millis(1)) {
+          Err(RecvTimeoutError::Timeout) => continue,
+          Err(RecvTimeoutError::Disconnected) => break,
+          Ok(_) => unreachable!(),
+        }
+      }
+      handle.join().unwrap();
+    }
+  }
+}
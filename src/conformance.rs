@@ -0,0 +1,964 @@
+// 所有线性结构共用的一致性测试：用同一串随机操作同时驱动被测的链表和 VecDeque，
+// 每一步比较返回值，最后比较剩下的元素
+//
+// 每个结构只支持一部分操作，不支持的操作 apply 返回 None，模型也跳过这一步。
+// 栈把 push/pop 映射成 VecDeque 的前端，队列是后进前出
+//
+// safe_deque_bad 不在这里：它的 push/pop 要求 &'a mut self，第一次调用之后链表就一直被借用着，
+// 连第二次 push 都写不出来（它自己的测试也因此被注释掉了）
+//
+// 这些测试也是给 Miri 跑的，Miri 下步数会少一些：
+//   cargo +nightly miri test conformance
+//   MIRIFLAGS="-Zmiri-tree-borrows" cargo +nightly miri test conformance
+
+use std::cell::Cell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::test_utils::{assert_panics_without_leak, live_allocations, live_markers, Marker, Page, PanicOnDrop};
+use crate::traits;
+use crate::{bounded_queue, deque, finger_tree, persistent_queue, persistent_stack, stack_bad, stack_ok};
+use crate::unsafe_deque_ok::{unsafe_deque_bad, unsafe_deque_ok};
+
+const STEPS: usize = if cfg!(miri) { 300 } else { 5000 };
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+  PushFront(i32),
+  PushBack(i32),
+  PopFront,
+  PopBack,
+  PeekFront,
+  PeekBack,
+  // 通过 peek_mut 一类的接口给一端的元素加上一个数
+  AddFront(i32),
+  AddBack(i32),
+  Iter,
+  IterMut(i32),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Out {
+  Unit,
+  Elem(Option<i32>),
+  Elems(Vec<i32>),
+}
+
+trait Subject {
+  const NAME: &'static str;
+
+  fn new() -> Self;
+  fn apply(&mut self, op: Op) -> Option<Out>;
+  // 从前往后取出剩下的所有元素
+  fn drain(&mut self) -> Vec<i32>;
+  // 结构不变量，每隔若干步检查一次
+  fn check(&self) {}
+}
+
+struct Rng(u64);
+
+impl Rng {
+  fn below(&mut self, n: usize) -> usize {
+    self.0 ^= self.0 << 13;
+    self.0 ^= self.0 >> 7;
+    self.0 ^= self.0 << 17;
+    (self.0 % n as u64) as usize
+  }
+
+  fn op(&mut self) -> Op {
+    let x = self.below(1000) as i32;
+    let d = self.below(10) as i32;
+    // push 的概率比 pop 高一些，让链表有机会变长
+    match self.below(14) {
+      0 | 1 => Op::PushFront(x),
+      2 | 3 => Op::PushBack(x),
+      4 => Op::PopFront,
+      5 => Op::PopBack,
+      6 => Op::PeekFront,
+      7 => Op::PeekBack,
+      8 => Op::AddFront(d),
+      9 => Op::AddBack(d),
+      10 => Op::Iter,
+      11 => Op::IterMut(d),
+      12 => Op::PushFront(x),
+      _ => Op::PushBack(x),
+    }
+  }
+}
+
+fn model(queue: &mut VecDeque<i32>, op: Op) -> Out {
+  match op {
+    Op::PushFront(x) => {
+      queue.push_front(x);
+      Out::Unit
+    },
+    Op::PushBack(x) => {
+      queue.push_back(x);
+      Out::Unit
+    },
+    Op::PopFront => Out::Elem(queue.pop_front()),
+    Op::PopBack => Out::Elem(queue.pop_back()),
+    Op::PeekFront => Out::Elem(queue.front().copied()),
+    Op::PeekBack => Out::Elem(queue.back().copied()),
+    Op::AddFront(d) => {
+      if let Some(x) = queue.front_mut() {
+        *x += d;
+      }
+      Out::Elem(queue.front().copied())
+    },
+    Op::AddBack(d) => {
+      if let Some(x) = queue.back_mut() {
+        *x += d;
+      }
+      Out::Elem(queue.back().copied())
+    },
+    Op::Iter => Out::Elems(queue.iter().copied().collect()),
+    Op::IterMut(d) => {
+      queue.iter_mut().for_each(|x| *x += d);
+      Out::Elems(queue.iter().copied().collect())
+    },
+  }
+}
+
+fn run<S: Subject>() {
+  for seed in [0x2545_f491_4f6c_dd1d, 0x9e37_79b9_7f4a_7c15, 1] {
+    let mut rng = Rng(seed);
+    let mut subject = S::new();
+    let mut queue = VecDeque::new();
+    for step in 0..STEPS {
+      let op = rng.op();
+      if let Some(out) = subject.apply(op) {
+        assert_eq!(out, model(&mut queue, op), "{} step {} {:?}", S::NAME, step, op);
+      }
+      if step % 64 == 0 {
+        subject.check();
+      }
+    }
+    subject.check();
+    assert_eq!(subject.drain(), Vec::from(queue), "{} drain", S::NAME);
+  }
+}
+
+struct StackBad(stack_bad::List);
+
+impl Subject for StackBad {
+  const NAME: &'static str = "stack_bad";
+
+  fn new() -> Self {
+    StackBad(stack_bad::List::new())
+  }
+
+  fn apply(&mut self, op: Op) -> Option<Out> {
+    Some(match op {
+      Op::PushFront(x) => {
+        self.0.push(x);
+        Out::Unit
+      },
+      Op::PopFront => Out::Elem(self.0.pop()),
+      _ => return None,
+    })
+  }
+
+  fn drain(&mut self) -> Vec<i32> {
+    std::iter::from_fn(|| self.0.pop()).collect()
+  }
+}
+
+struct StackOk(stack_ok::List<i32>);
+
+impl Subject for StackOk {
+  const NAME: &'static str = "stack_ok";
+
+  fn new() -> Self {
+    StackOk(stack_ok::List::new())
+  }
+
+  fn apply(&mut self, op: Op) -> Option<Out> {
+    Some(match op {
+      Op::PushFront(x) => {
+        self.0.push(x);
+        Out::Unit
+      },
+      Op::PopFront => Out::Elem(self.0.pop()),
+      Op::PeekFront => Out::Elem(self.0.peek().copied()),
+      Op::AddFront(d) => {
+        if let Some(x) = self.0.peek_mut() {
+          *x += d;
+        }
+        Out::Elem(self.0.peek().copied())
+      },
+      Op::Iter => Out::Elems(self.0.iter().copied().collect()),
+      Op::IterMut(d) => {
+        self.0.iter_mut().for_each(|x| *x += d);
+        Out::Elems(self.0.iter().copied().collect())
+      },
+      _ => return None,
+    })
+  }
+
+  fn drain(&mut self) -> Vec<i32> {
+    std::mem::take(&mut self.0).into_iter().collect()
+  }
+}
+
+// 除了当前版本，还记下每一个历史版本和它当时的内容，最后检查它们都没有被改动
+struct PersistentStack {
+  list: persistent_stack::List<i32>,
+  versions: Vec<(persistent_stack::List<i32>, Vec<i32>)>,
+}
+
+impl Subject for PersistentStack {
+  const NAME: &'static str = "persistent_stack";
+
+  fn new() -> Self {
+    PersistentStack { list: persistent_stack::List::new(), versions: Vec::new() }
+  }
+
+  fn apply(&mut self, op: Op) -> Option<Out> {
+    let out = match op {
+      Op::PushFront(x) => {
+        self.list = self.list.prepend(x);
+        Out::Unit
+      },
+      Op::PopFront => {
+        let head = self.list.head().copied();
+        self.list = self.list.tail();
+        Out::Elem(head)
+      },
+      Op::PeekFront => Out::Elem(self.list.head().copied()),
+      Op::AddFront(d) => {
+        if let Some(x) = self.list.head_mut() {
+          *x += d;
+        }
+        Out::Elem(self.list.head().copied())
+      },
+      Op::Iter => Out::Elems(self.list.iter().copied().collect()),
+      Op::IterMut(d) => {
+        self.list.iter_mut().for_each(|x| *x += d);
+        Out::Elems(self.list.iter().copied().collect())
+      },
+      _ => return None,
+    };
+    self.versions.push((self.list.clone(), self.list.iter().copied().collect()));
+    Some(out)
+  }
+
+  fn drain(&mut self) -> Vec<i32> {
+    for (list, elems) in &self.versions {
+      assert_eq!(&list.iter().copied().collect::<Vec<_>>(), elems);
+    }
+    self.list.iter().copied().collect()
+  }
+}
+
+struct Deque(deque::List<i32>);
+
+impl Subject for Deque {
+  const NAME: &'static str = "deque";
+
+  fn new() -> Self {
+    Deque(deque::List::new())
+  }
+
+  fn apply(&mut self, op: Op) -> Option<Out> {
+    let peek = |elem: Option<std::cell::Ref<'_, i32>>| Out::Elem(elem.map(|x| *x));
+    Some(match op {
+      Op::PushFront(x) => {
+        self.0.push_front(x);
+        Out::Unit
+      },
+      Op::PushBack(x) => {
+        self.0.push_back(x);
+        Out::Unit
+      },
+      Op::PopFront => Out::Elem(self.0.pop_front()),
+      Op::PopBack => Out::Elem(self.0.pop_back()),
+      Op::PeekFront => peek(self.0.peek_front()),
+      Op::PeekBack => peek(self.0.peek_back()),
+      Op::AddFront(d) => {
+        if let Some(mut x) = self.0.peek_front_mut() {
+          *x += d;
+        }
+        peek(self.0.peek_front())
+      },
+      Op::AddBack(d) => {
+        if let Some(mut x) = self.0.peek_back_mut() {
+          *x += d;
+        }
+        peek(self.0.peek_back())
+      },
+      _ => return None,
+    })
+  }
+
+  fn drain(&mut self) -> Vec<i32> {
+    std::mem::take(&mut self.0).into_iter().collect()
+  }
+
+  fn check(&self) {
+    let report = self.0.audit();
+    assert!(report.is_healthy(), "{:?}", report);
+  }
+}
+
+struct UnsafeDequeOk(unsafe_deque_ok::List<i32>);
+
+impl Subject for UnsafeDequeOk {
+  const NAME: &'static str = "unsafe_deque_ok";
+
+  fn new() -> Self {
+    UnsafeDequeOk(unsafe_deque_ok::List::new())
+  }
+
+  fn apply(&mut self, op: Op) -> Option<Out> {
+    Some(match op {
+      Op::PushFront(x) => {
+        self.0.push_front(x);
+        Out::Unit
+      },
+      Op::PushBack(x) => {
+        self.0.push(x);
+        Out::Unit
+      },
+      Op::PopFront => Out::Elem(self.0.pop()),
+      Op::PopBack => Out::Elem(self.0.pop_back()),
+      Op::PeekFront => Out::Elem(self.0.peek().copied()),
+      Op::PeekBack => Out::Elem(self.0.peek_back().copied()),
+      Op::AddFront(d) => {
+        if let Some(x) = self.0.peek_mut() {
+          *x += d;
+        }
+        Out::Elem(self.0.peek().copied())
+      },
+      Op::AddBack(d) => {
+        if let Some(x) = self.0.peek_back_mut() {
+          *x += d;
+        }
+        Out::Elem(self.0.peek_back().copied())
+      },
+      Op::Iter => Out::Elems(self.0.iter().copied().collect()),
+      Op::IterMut(d) => {
+        self.0.iter_mut().for_each(|x| *x += d);
+        Out::Elems(self.0.iter().copied().collect())
+      },
+    })
+  }
+
+  fn drain(&mut self) -> Vec<i32> {
+    std::mem::take(&mut self.0).into_iter().collect()
+  }
+}
+
+// 行为上是个正常的队列，只是 push 违反了 Stacked Borrows，见 unsafe_deque_bad.rs
+struct UnsafeDequeBad(unsafe_deque_bad::List<i32>);
+
+impl Subject for UnsafeDequeBad {
+  const NAME: &'static str = "unsafe_deque_bad";
+
+  fn new() -> Self {
+    UnsafeDequeBad(unsafe_deque_bad::List::new())
+  }
+
+  fn apply(&mut self, op: Op) -> Option<Out> {
+    Some(match op {
+      Op::PushBack(x) => {
+        self.0.push(x);
+        Out::Unit
+      },
+      Op::PopFront => Out::Elem(self.0.pop()),
+      _ => return None,
+    })
+  }
+
+  fn drain(&mut self) -> Vec<i32> {
+    std::iter::from_fn(|| self.0.pop()).collect()
+  }
+}
+
+// 满了之后 push 被拒绝，这一步不算进模型
+struct BoundedQueue(bounded_queue::BoundedQueue<i32>);
+
+impl Subject for BoundedQueue {
+  const NAME: &'static str = "bounded_queue";
+
+  fn new() -> Self {
+    BoundedQueue(bounded_queue::BoundedQueue::new(16))
+  }
+
+  fn apply(&mut self, op: Op) -> Option<Out> {
+    Some(match op {
+      Op::PushBack(x) => {
+        let full = self.0.is_full();
+        match self.0.push(x) {
+          Ok(None) if !full => Out::Unit,
+          Err(rejected) if full => {
+            assert_eq!(rejected, x);
+            return None;
+          },
+          result => panic!("unexpected push result {:?} (full: {})", result, full),
+        }
+      },
+      Op::PopFront => Out::Elem(self.0.pop()),
+      Op::PeekFront => Out::Elem(self.0.peek().copied()),
+      Op::AddFront(d) => {
+        if let Some(x) = self.0.peek_mut() {
+          *x += d;
+        }
+        Out::Elem(self.0.peek().copied())
+      },
+      _ => return None,
+    })
+  }
+
+  fn drain(&mut self) -> Vec<i32> {
+    std::iter::from_fn(|| self.0.pop()).collect()
+  }
+
+  fn check(&self) {
+    assert!(self.0.len() <= self.0.capacity());
+  }
+}
+
+struct PersistentQueue(persistent_queue::PersistentQueue<i32>);
+
+impl Subject for PersistentQueue {
+  const NAME: &'static str = "persistent_queue";
+
+  fn new() -> Self {
+    PersistentQueue(persistent_queue::PersistentQueue::new())
+  }
+
+  fn apply(&mut self, op: Op) -> Option<Out> {
+    Some(match op {
+      Op::PushBack(x) => {
+        self.0 = self.0.push_back(x);
+        Out::Unit
+      },
+      Op::PopFront => {
+        let (front, rest) = match self.0.pop_front() {
+          Some((front, rest)) => (Some(*front), rest),
+          None => (None, self.0.clone()),
+        };
+        self.0 = rest;
+        Out::Elem(front)
+      },
+      Op::PeekFront => Out::Elem(self.0.peek_front().copied()),
+      Op::Iter => Out::Elems(self.0.iter().copied().collect()),
+      _ => return None,
+    })
+  }
+
+  fn drain(&mut self) -> Vec<i32> {
+    self.0.iter().copied().collect()
+  }
+}
+
+struct RealTimeQueue(persistent_queue::RealTimeQueue<i32>);
+
+impl Subject for RealTimeQueue {
+  const NAME: &'static str = "real_time_queue";
+
+  fn new() -> Self {
+    RealTimeQueue(persistent_queue::RealTimeQueue::new())
+  }
+
+  fn apply(&mut self, op: Op) -> Option<Out> {
+    Some(match op {
+      Op::PushBack(x) => {
+        self.0 = self.0.push_back(x);
+        Out::Unit
+      },
+      Op::PopFront => {
+        let (front, rest) = match self.0.pop_front() {
+          Some((front, rest)) => (Some(*front), rest),
+          None => (None, self.0.clone()),
+        };
+        self.0 = rest;
+        Out::Elem(front)
+      },
+      Op::PeekFront => Out::Elem(self.0.peek_front().copied()),
+      Op::Iter => Out::Elems(self.0.clone().into_iter().collect()),
+      _ => return None,
+    })
+  }
+
+  fn drain(&mut self) -> Vec<i32> {
+    self.0.clone().into_iter().collect()
+  }
+}
+
+struct FingerTree(finger_tree::FingerTree<i32>);
+
+impl Subject for FingerTree {
+  const NAME: &'static str = "finger_tree";
+
+  fn new() -> Self {
+    FingerTree(finger_tree::FingerTree::new())
+  }
+
+  fn apply(&mut self, op: Op) -> Option<Out> {
+    Some(match op {
+      Op::PushFront(x) => {
+        self.0 = self.0.push_front(x);
+        Out::Unit
+      },
+      Op::PushBack(x) => {
+        self.0 = self.0.push_back(x);
+        Out::Unit
+      },
+      Op::PopFront => {
+        let (front, rest) = match self.0.pop_front() {
+          Some((front, rest)) => (Some(*front), rest),
+          None => (None, self.0.clone()),
+        };
+        self.0 = rest;
+        Out::Elem(front)
+      },
+      Op::PopBack => {
+        let (back, rest) = match self.0.pop_back() {
+          Some((back, rest)) => (Some(*back), rest),
+          None => (None, self.0.clone()),
+        };
+        self.0 = rest;
+        Out::Elem(back)
+      },
+      Op::PeekFront => Out::Elem(self.0.front().copied()),
+      Op::PeekBack => Out::Elem(self.0.back().copied()),
+      Op::Iter => Out::Elems(self.0.iter().copied().collect()),
+      _ => return None,
+    })
+  }
+
+  fn drain(&mut self) -> Vec<i32> {
+    self.0.iter().copied().collect()
+  }
+}
+
+#[test]
+fn stack_bad() {
+  run::<StackBad>();
+}
+
+#[test]
+fn stack_ok() {
+  run::<StackOk>();
+}
+
+#[test]
+fn persistent_stack() {
+  run::<PersistentStack>();
+}
+
+#[test]
+fn deque() {
+  run::<Deque>();
+}
+
+#[test]
+fn unsafe_deque_ok() {
+  run::<UnsafeDequeOk>();
+}
+
+// Miri 下会在 push 里报出 UB，这正是这个反面例子要演示的，所以只在原生环境下跑
+#[test]
+#[cfg_attr(miri, ignore)]
+fn unsafe_deque_bad() {
+  run::<UnsafeDequeBad>();
+}
+
+#[test]
+fn bounded_queue() {
+  run::<BoundedQueue>();
+}
+
+#[test]
+fn persistent_queue() {
+  run::<PersistentQueue>();
+  run::<RealTimeQueue>();
+}
+
+#[test]
+fn finger_tree() {
+  run::<FingerTree>();
+}
+
+// 下面是迭代器和修改交替进行的边界情况，借用检查保证不会有悬垂的迭代器，
+// 这里主要是让 Miri 检查裸指针的别名规则和泄漏
+
+#[test]
+fn unsafe_deque_iter_interleaving() {
+  let mut list = unsafe_deque_ok::List::new();
+  assert_eq!(list.iter().next(), None);
+  assert_eq!(list.iter_mut().next(), None);
+
+  list.push(1);
+  list.push(2);
+  list.push(3);
+
+  // 只走一半就丢掉的 IterMut，之后 tail 指针仍然可用
+  for x in list.iter_mut().take(2) {
+    *x *= 10;
+  }
+  list.push(4);
+  assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![10, 20, 3, 4]);
+
+  // peek_mut 之后 push，再从头 pop 到空，然后在空链表上重新 push
+  *list.peek_mut().unwrap() += 1;
+  list.push(5);
+  assert_eq!(list.pop(), Some(11));
+  let mut iter = list.iter();
+  assert_eq!(iter.next(), Some(&20));
+  assert_eq!(iter.next(), Some(&3));
+  while list.pop().is_some() {}
+  assert_eq!(list.peek(), None);
+  list.push(6);
+  assert_eq!(list.iter_mut().next(), Some(&mut 6));
+  assert_eq!(list.peek(), Some(&6));
+
+  // IntoIter 没有走完就被 drop，剩下的节点也要释放
+  let mut list = unsafe_deque_ok::List::new();
+  list.push(String::from("a"));
+  list.push(String::from("b"));
+  let mut into_iter = list.into_iter();
+  assert_eq!(into_iter.next().as_deref(), Some("a"));
+}
+
+#[test]
+fn stack_iter_interleaving() {
+  let mut list = stack_ok::List::new();
+  list.push(1);
+  list.push(2);
+  list.push(3);
+  if let Some(x) = list.iter_mut().nth(1) {
+    *x = 20;
+  }
+  assert_eq!(list.pop(), Some(3));
+  list.push(4);
+  assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![4, 20, 1]);
+
+  let mut list = stack_ok::List::new();
+  list.push(vec![1]);
+  list.push(vec![2]);
+  let mut into_iter = list.into_iter();
+  assert_eq!(into_iter.next(), Some(vec![2]));
+}
+
+#[test]
+fn deque_single_node_peeks() {
+  // 只有一个节点时 head 和 tail 是同一个 RefCell
+  let mut list = deque::List::new();
+  list.push_back(1);
+  {
+    let front = list.peek_front().unwrap();
+    let back = list.peek_back().unwrap();
+    assert_eq!((*front, *back), (1, 1));
+  }
+  *list.peek_back_mut().unwrap() = 2;
+  assert_eq!(list.pop_front(), Some(2));
+  assert!(list.audit().is_healthy());
+
+  let mut list = deque::List::new();
+  list.push_back(String::from("a"));
+  list.push_back(String::from("b"));
+  list.push_front(String::from("c"));
+  let probe = list.watch();
+  let mut into_iter = list.into_iter();
+  assert_eq!(into_iter.next().as_deref(), Some("c"));
+  drop(into_iter);
+  assert_eq!(probe.alive(), 0);
+}
+
+#[test]
+fn persistent_iter_outlives_versions() {
+  let list = persistent_stack::List::new().prepend(1).prepend(2).prepend(3);
+  let tail = list.tail();
+  drop(list);
+  // 原来的头节点释放了，tail 的节点还在
+  assert_eq!(tail.iter().copied().collect::<Vec<_>>(), vec![2, 1]);
+
+  let tree: finger_tree::FingerTree<_> = (0..50).collect();
+  let (left, right) = tree.split_at(20);
+  drop(tree);
+  let joined = right.concat(&left);
+  drop((left, right));
+  assert_eq!(joined.iter().copied().collect::<Vec<_>>(), (20..50).chain(0..20).collect::<Vec<_>>());
+}
+
+// 各个链表手写的 Debug、PartialEq、Hash、Clone 都沿着 next 循环，一百万个节点也不会爆栈。
+// from 依次压入 range 里的元素，reversed 表示压入之后从前往后看是逆序的（栈从表头压入）
+
+const LONG_LEN: i32 = 1_000_000;
+
+fn hash<L: Hash>(list: &L) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  list.hash(&mut hasher);
+  hasher.finish()
+}
+
+fn pushed<S: traits::Stack<i32>>(range: Range<i32>) -> S {
+  let mut stack = S::default();
+  range.for_each(|i| stack.push(i));
+  stack
+}
+
+fn long_list<L: Clone + Debug + Hash + PartialEq>(from: impl Fn(Range<i32>) -> L, reversed: bool) {
+  let list = from(0..LONG_LEN);
+  // 内容相同但节点不共享，必须逐个比较
+  let rebuilt = from(0..LONG_LEN);
+  assert_eq!(list, list.clone());
+  assert_eq!(list, rebuilt);
+  assert_eq!(hash(&list), hash(&rebuilt));
+  drop(rebuilt);
+  assert_ne!(list, from(0..LONG_LEN - 1));
+  assert_ne!(from(0..3), from(1..4));
+
+  let mut expected: Vec<i32> = (0..LONG_LEN).collect();
+  if reversed {
+    expected.reverse();
+  }
+  assert_eq!(format!("{:?}", list), format!("{:?}", expected));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn long_stack_bad() {
+  long_list(pushed::<stack_bad::List>, true);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn long_stack_ok() {
+  long_list(pushed::<stack_ok::List<i32>>, true);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn long_persistent_stack() {
+  long_list(|range| range.fold(persistent_stack::List::new(), |list, i| list.prepend(i)), true);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn long_deque() {
+  long_list(pushed::<deque::List<i32>>, false);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn long_unsafe_deque_ok() {
+  long_list(pushed::<unsafe_deque_ok::List<i32>>, false);
+}
+
+// 元素的 drop panic 时，其它元素照样被释放，节点也不能泄漏
+
+fn panicking<S: traits::Stack<PanicOnDrop>>(drops: &Rc<Cell<usize>>, panic_at: usize) -> S {
+  let mut stack = S::default();
+  (0..5).for_each(|i| stack.push(PanicOnDrop::new(drops, i == panic_at)));
+  stack
+}
+
+fn drop_panic<S: traits::Stack<PanicOnDrop> + IntoIterator<Item = PanicOnDrop>>() {
+  let drops = Rc::new(Cell::new(0));
+  assert_panics_without_leak(|| drop(panicking::<S>(&drops, 2)));
+  assert_eq!(drops.get(), 5);
+
+  // IntoIter 没走完就被 drop 也一样
+  let drops = Rc::new(Cell::new(0));
+  assert_panics_without_leak(|| {
+    let mut into_iter = panicking::<S>(&drops, 3).into_iter();
+    drop(into_iter.next());
+    drop(into_iter);
+  });
+  assert_eq!(drops.get(), 5);
+}
+
+fn drop_panic_persistent<P: traits::PersistentStack<PanicOnDrop>>() {
+  let drops = Rc::new(Cell::new(0));
+  assert_panics_without_leak(|| {
+    let list = (0..5).fold(P::default(), |list, i| list.push(PanicOnDrop::new(&drops, i == 2)));
+    drop(list);
+  });
+  assert_eq!(drops.get(), 5);
+
+  // 被其它版本共享的后缀不能释放
+  let drops = Rc::new(Cell::new(0));
+  let shared = P::default().push(PanicOnDrop::new(&drops, false));
+  assert_panics_without_leak(|| {
+    let list = shared.push(PanicOnDrop::new(&drops, false)).push(PanicOnDrop::new(&drops, true));
+    drop(list);
+  });
+  assert_eq!(drops.get(), 2);
+  assert_eq!(shared.len(), 1);
+}
+
+#[test]
+fn drop_panics() {
+  drop_panic::<stack_ok::List<_>>();
+  drop_panic::<deque::List<_>>();
+  drop_panic::<unsafe_deque_ok::List<_>>();
+  drop_panic_persistent::<persistent_stack::List<_>>();
+  drop_panic_persistent::<finger_tree::FingerTree<_>>();
+}
+
+// 零大小和按页对齐的元素：每个结构都用同一组泛型检查走一遍，
+// 零大小的看 drop 次数，对齐的看元素地址。Miri 会检查悬垂指针和对齐
+
+const LAYOUT_LEN: usize = 64;
+
+fn layout_stack<S: traits::Stack<E>, E>(new: impl Fn(usize) -> E, check: impl Fn(&E, usize)) {
+  let mut stack = S::default();
+  for i in 0..LAYOUT_LEN {
+    stack.push(new(i));
+    check(&*stack.peek().unwrap(), i);
+  }
+  assert_eq!(stack.len(), LAYOUT_LEN);
+  for i in (0..LAYOUT_LEN).rev() {
+    check(&stack.pop().unwrap(), i);
+  }
+  assert!(stack.pop().is_none());
+  // 没弹出的元素交给 Drop
+  stack.push(new(0));
+}
+
+fn layout_deque<D: traits::Deque<E>, E>(new: impl Fn(usize) -> E, check: impl Fn(&E, usize)) {
+  let mut deque = D::default();
+  for i in 0..LAYOUT_LEN {
+    deque.push_back(new(i));
+    check(&*deque.peek_back().unwrap(), i);
+    deque.push_front(new(i));
+    check(&*deque.peek_front().unwrap(), i);
+  }
+  for i in (0..LAYOUT_LEN).rev() {
+    check(&deque.pop_front().unwrap(), i);
+    check(&deque.pop_back().unwrap(), i);
+  }
+  assert!(deque.pop_front().is_none() && deque.pop_back().is_none());
+  deque.push_back(new(0));
+}
+
+fn layout_persistent<P: traits::PersistentStack<E>, E>(new: impl Fn(usize) -> E, check: impl Fn(&E, usize)) {
+  let mut versions = vec![P::default()];
+  for i in 0..LAYOUT_LEN {
+    let next = versions[i].push(new(i));
+    versions.push(next);
+  }
+  for (i, version) in versions.iter().enumerate().skip(1) {
+    check(version.peek().unwrap(), i - 1);
+    let (top, rest) = version.pop().unwrap();
+    check(top, i - 1);
+    assert_eq!(rest.len(), i - 1);
+  }
+}
+
+// 每个结构都用 Marker 跑一遍，结束时所有 Marker（包括内部 clone 出来的）都要被 drop
+fn zero_sized(run: impl FnOnce(&dyn Fn(usize) -> Marker)) {
+  let live = live_markers();
+  run(&|_| Marker::new());
+  assert_eq!(live_markers(), live);
+}
+
+// stack_ok 和 unsafe_deque_ok 存零大小元素时不分配节点，只改 len。
+// iterate 返回 iter 和 iter_mut 一共走过的元素数
+fn zero_sized_list<S>(push: fn(&mut S, Marker), iterate: fn(&mut S) -> usize)
+where
+  S: traits::Stack<Marker> + Clone + PartialEq + Debug + IntoIterator<Item = Marker>,
+{
+  let live = live_markers();
+  let before = live_allocations();
+  let mut list = S::default();
+  (0..1000).for_each(|_| push(&mut list, Marker::new()));
+  assert_eq!(live_allocations(), before);
+  assert_eq!(list.len(), 1000);
+  assert!(list.peek().is_some());
+  assert_eq!(iterate(&mut list), 2000);
+
+  // 每个值正好 drop 一次
+  let copy = list.clone();
+  assert_eq!(list, copy);
+  assert_eq!(live_markers(), live + 2000);
+  drop(list.pop());
+  assert_eq!(live_markers(), live + 1999);
+  drop(copy);
+  assert_eq!(list.into_iter().take(10).count(), 10);
+  assert_eq!(live_markers(), live);
+}
+
+fn aligned(page: &Page, i: usize) {
+  assert!(page.is_aligned());
+  assert_eq!(page.0, i);
+}
+
+#[test]
+fn zero_sized_elements() {
+  let ignore = |_: &Marker, _| {};
+  zero_sized(|new| layout_stack::<stack_ok::List<_>, _>(new, ignore));
+  zero_sized(|new| layout_stack::<deque::List<_>, _>(new, ignore));
+  zero_sized(|new| layout_stack::<unsafe_deque_ok::List<_>, _>(new, ignore));
+  zero_sized(|new| layout_deque::<deque::List<_>, _>(new, ignore));
+  zero_sized(|new| layout_deque::<unsafe_deque_ok::List<_>, _>(new, ignore));
+  zero_sized(|new| layout_persistent::<persistent_stack::List<_>, _>(new, ignore));
+  zero_sized(|new| layout_persistent::<finger_tree::FingerTree<_>, _>(new, ignore));
+
+  zero_sized(|new| {
+    let mut queue = bounded_queue::BoundedQueue::new(4);
+    for i in 0..LAYOUT_LEN {
+      let _ = queue.push(new(i));
+    }
+    assert_eq!(queue.len(), 4);
+    assert!(queue.pop().is_some());
+  });
+  zero_sized(|new| {
+    let queue = (0..LAYOUT_LEN).fold(persistent_queue::PersistentQueue::new(), |q, i| q.push_back(new(i)));
+    assert_eq!(queue.len(), LAYOUT_LEN);
+    assert_eq!(queue.iter().count(), LAYOUT_LEN);
+  });
+  zero_sized(|new| {
+    let queue = (0..LAYOUT_LEN).fold(persistent_queue::RealTimeQueue::new(), |q, i| q.push_back(new(i)));
+    assert_eq!(queue.pop_front().map(|(_, rest)| rest.len()), Some(LAYOUT_LEN - 1));
+  });
+
+  zero_sized_list(stack_ok::List::push, |list| list.iter().len() + list.iter_mut().count());
+  zero_sized_list(unsafe_deque_ok::List::push_front, |list| {
+    list.iter().rev().count() + list.iter_mut().rev().count()
+  });
+
+  let mut units = stack_ok::List::new();
+  units.push(());
+  assert_eq!(units.pop(), Some(()));
+  let mut units = unsafe_deque_ok::List::new();
+  units.push_front(());
+  assert_eq!(units.pop_back(), Some(()));
+  assert_eq!(units.peek_back(), None);
+}
+
+#[test]
+fn over_aligned_elements() {
+  layout_stack::<stack_ok::List<_>, _>(Page, aligned);
+  layout_stack::<deque::List<_>, _>(Page, aligned);
+  layout_stack::<unsafe_deque_ok::List<_>, _>(Page, aligned);
+  layout_deque::<deque::List<_>, _>(Page, aligned);
+  layout_deque::<unsafe_deque_ok::List<_>, _>(Page, aligned);
+  layout_persistent::<persistent_stack::List<_>, _>(Page, aligned);
+  layout_persistent::<finger_tree::FingerTree<_>, _>(Page, aligned);
+
+  // 迭代器交出的引用直接指向节点里的元素
+  let mut list = unsafe_deque_ok::List::new();
+  (0..LAYOUT_LEN).for_each(|i| list.push(Page(i)));
+  list.iter().enumerate().for_each(|(i, page)| aligned(page, i));
+  list.iter_mut().rev().for_each(|page| assert!(page.is_aligned()));
+  let tree: finger_tree::FingerTree<_> = (0..LAYOUT_LEN).map(Page).collect();
+  tree.iter().enumerate().for_each(|(i, page)| aligned(page, i));
+  assert!(tree.get(LAYOUT_LEN / 2).unwrap().is_aligned());
+
+  let mut queue = bounded_queue::BoundedQueue::new(LAYOUT_LEN);
+  (0..LAYOUT_LEN).for_each(|i| queue.push(Page(i)).unwrap().map_or((), drop));
+  aligned(queue.peek().unwrap(), 0);
+  let queue = (0..LAYOUT_LEN).fold(persistent_queue::PersistentQueue::new(), |q, i| q.push_back(Page(i)));
+  queue.iter().enumerate().for_each(|(i, page)| aligned(page, i));
+  let queue = (0..LAYOUT_LEN).fold(persistent_queue::RealTimeQueue::new(), |q, i| q.push_back(Page(i)));
+  aligned(queue.peek_front().unwrap(), 0);
+}
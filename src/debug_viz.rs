@@ -0,0 +1,291 @@
+// 把链表的节点图画成 Graphviz 的 DOT 或者 ASCII，用来观察共享和环
+// 节点按地址去重，多个 persistent_stack::List 画进同一个 Graph 时共享的节点只出现一次
+//
+// 各个链表在自己的模块里实现 Visualize，只负责把节点和边登记到 Graph 里
+// persistent_queue 的两种队列画成 front/rear 两个入口；finger_tree 是树不是链表，没有实现 Visualize
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+  Next,
+  Prev,
+}
+
+struct VizNode {
+  address: usize,
+  label: String,
+  // Rc 的强引用计数，裸指针和 Box 节点没有
+  strong: Option<usize>,
+}
+
+struct Root {
+  name: String,
+  head: Option<usize>,
+  tail: Option<usize>,
+}
+
+pub struct Graph {
+  nodes: Vec<VizNode>,
+  index: HashMap<usize, usize>,
+  edges: Vec<(usize, usize, EdgeKind)>,
+  roots: Vec<Root>,
+}
+
+pub trait Visualize {
+  // 以 name 为入口，把自己的节点登记到 graph 里
+  fn visualize(&self, name: &str, graph: &mut Graph);
+
+  fn to_dot(&self) -> String {
+    let mut graph = Graph::new();
+    self.visualize("list", &mut graph);
+    graph.to_dot()
+  }
+
+  fn to_ascii(&self) -> String {
+    let mut graph = Graph::new();
+    self.visualize("list", &mut graph);
+    graph.to_ascii()
+  }
+}
+
+impl Graph {
+  pub fn new() -> Self {
+    Graph { nodes: Vec::new(), index: HashMap::new(), edges: Vec::new(), roots: Vec::new() }
+  }
+
+  pub fn add<V: Visualize + ?Sized>(&mut self, name: &str, list: &V) -> &mut Self {
+    list.visualize(name, self);
+    self
+  }
+
+  // 节点已经在图里时返回 false，调用方据此停止遍历（遇到了共享的节点或者环）
+  pub fn add_node(&mut self, address: usize, label: String, strong: Option<usize>) -> bool {
+    if self.index.contains_key(&address) {
+      return false;
+    }
+    self.index.insert(address, self.nodes.len());
+    self.nodes.push(VizNode { address, label, strong });
+    true
+  }
+
+  pub fn add_edge(&mut self, from: usize, to: usize, kind: EdgeKind) {
+    self.edges.push((from, to, kind));
+  }
+
+  pub fn add_root(&mut self, name: &str, head: Option<usize>, tail: Option<usize>) {
+    self.roots.push(Root { name: name.to_string(), head, tail });
+  }
+
+  pub fn node_count(&self) -> usize {
+    self.nodes.len()
+  }
+
+  pub fn edge_count(&self) -> usize {
+    self.edges.len()
+  }
+
+  pub fn to_dot(&self) -> String {
+    let mut out = String::from("digraph lists {\n  rankdir=LR;\n  node [shape=box];\n");
+    for root in &self.roots {
+      let name = escape(&root.name);
+      writeln!(out, "  \"{}\" [shape=plaintext];", name).unwrap();
+      match root.head {
+        Some(head) => writeln!(out, "  \"{}\" -> n{:x} [label=\"head\"];", name, head).unwrap(),
+        None => writeln!(out, "  \"{}\" -> nil [label=\"head\"];", name).unwrap(),
+      }
+      if let Some(tail) = root.tail {
+        writeln!(out, "  \"{}\" -> n{:x} [label=\"tail\", style=dotted];", name, tail).unwrap();
+      }
+    }
+    if self.roots.iter().any(|root| root.head.is_none()) {
+      out.push_str("  nil [shape=point];\n");
+    }
+    for node in &self.nodes {
+      let count = node.strong.map(|count| format!("\\nrc={}", count)).unwrap_or_default();
+      writeln!(out, "  n{:x} [label=\"{}\\n{:#x}{}\"];", node.address, escape(&node.label), node.address, count).unwrap();
+    }
+    for (from, to, kind) in &self.edges {
+      match kind {
+        EdgeKind::Next => writeln!(out, "  n{:x} -> n{:x};", from, to).unwrap(),
+        EdgeKind::Prev => writeln!(out, "  n{:x} -> n{:x} [style=dashed, label=\"prev\"];", from, to).unwrap(),
+      }
+    }
+    out.push_str("}\n");
+    out
+  }
+
+  // 每个入口一行，沿 next 走：
+  //   a -> b  普通的 next
+  //   a <-> b b 的 prev 指回 a
+  //   (shared with x) 节点已经在前面名为 x 的链表里画过
+  //   (cycle to ...)  回到了本行画过的节点
+  // 节点的 prev 不是前一个节点时会额外标出 prev 指向哪里
+  pub fn to_ascii(&self) -> String {
+    let next: HashMap<usize, usize> = self.edges_of(EdgeKind::Next).collect();
+    let prev: HashMap<usize, usize> = self.edges_of(EdgeKind::Prev).collect();
+    let mut owner: HashMap<usize, &str> = HashMap::new();
+    let mut out = String::new();
+
+    for root in &self.roots {
+      write!(out, "{}: ", root.name).unwrap();
+      let mut seen = HashSet::new();
+      let mut current = root.head;
+      let mut before = None;
+      while let Some(address) = current {
+        if let Some(name) = owner.get(&address) {
+          write!(out, "{} (shared with {})", self.describe(address), name).unwrap();
+          break;
+        }
+        if !seen.insert(address) {
+          write!(out, "(cycle to {:#x})", address).unwrap();
+          break;
+        }
+
+        out.push_str(&self.describe(address));
+        match prev.get(&address) {
+          Some(target) if Some(*target) != before => write!(out, "(prev={:#x})", target).unwrap(),
+          _ => {}
+        }
+
+        before = current;
+        current = next.get(&address).copied();
+        match current {
+          Some(following) if prev.get(&following) == Some(&address) => out.push_str(" <-> "),
+          _ => out.push_str(" -> "),
+        }
+      }
+      if current.is_none() {
+        out.push_str("nil");
+      }
+      if let Some(tail) = root.tail {
+        write!(out, " (tail={:#x})", tail).unwrap();
+      }
+      out.push('\n');
+
+      for address in seen {
+        owner.insert(address, &root.name);
+      }
+    }
+    out
+  }
+
+  fn edges_of(&self, kind: EdgeKind) -> impl Iterator<Item = (usize, usize)> + '_ {
+    self.edges.iter().filter(move |edge| edge.2 == kind).map(|edge| (edge.0, edge.1))
+  }
+
+  fn describe(&self, address: usize) -> String {
+    match self.index.get(&address).map(|&i| &self.nodes[i]) {
+      Some(VizNode { label, strong: Some(count), .. }) => format!("[{} @{:#x} rc={}]", label, address, count),
+      Some(VizNode { label, strong: None, .. }) => format!("[{} @{:#x}]", label, address),
+      None => format!("[? @{:#x}]", address),
+    }
+  }
+}
+
+impl Default for Graph {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+fn escape(label: &str) -> String {
+  label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{deque, persistent_queue, persistent_stack, stack_ok, unsafe_deque_ok::unsafe_deque_ok};
+
+  #[test]
+  fn persistent_lists_share_nodes() {
+    // README 里的例子
+    let list2 = persistent_stack::List::new().prepend('D').prepend('C').prepend('B');
+    let list1 = list2.prepend('A');
+    let list3 = list2.prepend('X');
+
+    let mut graph = Graph::new();
+    graph.add("list1", &list1).add("list2", &list2).add("list3", &list3);
+    assert_eq!(graph.node_count(), 5);
+    // A->B, B->C, C->D, X->B
+    assert_eq!(graph.edge_count(), 4);
+
+    let ascii = graph.to_ascii();
+    let lines: Vec<_> = ascii.lines().collect();
+    assert!(lines[0].starts_with("list1: ['A' @"));
+    assert!(lines[0].ends_with("-> nil"));
+    assert!(lines[0].contains("['B' @") && lines[0].contains("rc=3]"));
+    assert!(lines[1].starts_with("list2: ['B' @") && lines[1].ends_with("(shared with list1)"));
+    assert!(lines[2].starts_with("list3: ['X' @") && lines[2].ends_with("(shared with list1)"));
+
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph lists {"));
+    assert_eq!(dot.matches("[label=\"head\"]").count(), 3);
+    assert_eq!(dot.matches("\\nrc=").count(), 5);
+  }
+
+  #[test]
+  fn deque_links_both_ways() {
+    let mut list = deque::List::new();
+    list.push_front(3);
+    list.push_front(2);
+    list.push_front(1);
+
+    let ascii = list.to_ascii();
+    assert_eq!(ascii.matches(" <-> ").count(), 2);
+    assert!(!ascii.contains("prev="));
+    assert!(ascii.contains("(tail="));
+
+    let mut graph = Graph::new();
+    graph.add("deque", &list);
+    assert_eq!(graph.node_count(), 3);
+    assert_eq!(graph.edge_count(), 4);
+    assert_eq!(graph.to_dot().matches("label=\"prev\"").count(), 2);
+  }
+
+  #[test]
+  fn owned_lists() {
+    let mut stack = stack_ok::List::new();
+    stack.push("a\"b");
+    stack.push("c");
+    let ascii = stack.to_ascii();
+    assert!(ascii.starts_with("list: [\"c\" @"));
+    assert!(!ascii.contains("rc="));
+    // label 里的引号要转义
+    assert!(stack.to_dot().contains("\\\"a\\\\\\\"b\\\""));
+
+    let mut queue = unsafe_deque_ok::List::new();
+    queue.push(1);
+    queue.push(2);
+    let ascii = queue.to_ascii();
+    assert!(ascii.starts_with("list: [1 @") && ascii.contains("-> [2 @") && ascii.contains("-> nil (tail="));
+
+    let empty = persistent_stack::List::<i32>::new();
+    assert_eq!(empty.to_ascii(), "list: nil\n");
+    assert!(empty.to_dot().contains("nil [shape=point]"));
+  }
+
+  #[test]
+  fn persistent_queues_share_nodes() {
+    let queue = persistent_queue::PersistentQueue::new().push_back(1).push_back(2).push_back(3);
+    let next = queue.push_back(4);
+
+    let mut graph = Graph::new();
+    graph.add("old", &queue).add("new", &next);
+    // 1 2 3 在 front 里被两个版本共享，4 在新版本的 rear 里
+    assert_eq!(graph.node_count(), 4);
+    let ascii = graph.to_ascii();
+    let lines: Vec<_> = ascii.lines().collect();
+    assert!(lines[0].starts_with("old.front: [1 @") && lines[0].ends_with("-> nil"));
+    assert_eq!(lines[1], "old.rear: nil");
+    assert!(lines[2].starts_with("new.front: [1 @") && lines[2].ends_with("(shared with old.front)"));
+    assert!(lines[3].starts_with("new.rear: [4 @") && lines[3].ends_with("-> nil"));
+
+    let queue = persistent_queue::RealTimeQueue::new().push_back(1).push_back(2);
+    let ascii = queue.to_ascii();
+    assert!(ascii.starts_with("list.front: [1 @"));
+    assert!(ascii.contains("\nlist.rear: [2 @"));
+  }
+}
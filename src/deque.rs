@@ -1,11 +1,24 @@
-use std::rc::Rc;
+use std::collections::{HashMap, HashSet};
+use std::rc::{Rc, Weak};
 use std::cell::{ RefCell, Ref, RefMut };
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::debug_viz::{EdgeKind, Graph, Visualize};
+use crate::traits;
+use crate::util;
 
 pub struct List<T> {
   head: Link<T>,
   tail: Link<T>,
+  len: usize,
+  id: u64,
 }
 
+// 用来区分 NodeHandle 属于哪个链表
+static NEXT_LIST_ID: AtomicU64 = AtomicU64::new(0);
+
 pub type Link<T> = Option<Rc<RefCell<Node<T>>>>;
 
 pub struct Node<T> {
@@ -22,6 +35,22 @@ impl<T> Node<T> {
       next: None
     }))
   }
+
+  pub(crate) fn elem(&self) -> &T {
+    &self.elem
+  }
+
+  pub(crate) fn elem_mut(&mut self) -> &mut T {
+    &mut self.elem
+  }
+
+  pub(crate) fn next_node(&self) -> Option<&Rc<RefCell<Node<T>>>> {
+    self.next.as_ref()
+  }
+
+  pub(crate) fn into_elem(self) -> T {
+    self.elem
+  }
 }
 
 impl<T> List<T> {
@@ -29,6 +58,8 @@ impl<T> List<T> {
     Self {
       head: None,
       tail: None,
+      len: 0,
+      id: NEXT_LIST_ID.fetch_add(1, Ordering::Relaxed),
     }
   }
 
@@ -45,6 +76,7 @@ impl<T> List<T> {
         self.tail = Some(new_node.clone());
       }
     }
+    self.len += 1;
   }
 
   pub fn push_back(&mut self, elem: T) {
@@ -52,7 +84,7 @@ impl<T> List<T> {
     match self.tail.take() {
       Some(old_tail) => {
         old_tail.borrow_mut().next = Some(new_node.clone());
-        new_node.borrow_mut().prev = Some(new_node.clone());
+        new_node.borrow_mut().prev = Some(old_tail);
         self.tail = Some(new_node);
       },
       None => {
@@ -60,6 +92,7 @@ impl<T> List<T> {
         self.tail = Some(new_node.clone());
       }
     }
+    self.len += 1;
   }
 
   pub fn pop_front(&mut self) -> Option<T> {
@@ -81,6 +114,7 @@ impl<T> List<T> {
       // Result<T, E> 中 T 要实现 Debug trait 才能使用 unwrap
       // Rc::try_unwrap(old_head).unwrap().into_inner().elem
 
+      self.len -= 1;
       Rc::try_unwrap(old_head).ok().unwrap().into_inner().elem
     })
   }
@@ -96,11 +130,20 @@ impl<T> List<T> {
           self.head.take();
         }
       }
+      self.len -= 1;
       Rc::try_unwrap(old_tail).ok().unwrap().into_inner().elem
     })
   }
 
-  pub fn peek_front(&self) -> Option<Ref<T>> {
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn peek_front(&self) -> Option<Ref<'_, T>> {
     self.head.as_ref().map(|node|
       // cannot return value referencing temporary value
       // &node.borrow().elem
@@ -115,47 +158,642 @@ impl<T> List<T> {
     )
   }
 
-  pub fn peek_back(&self) -> Option<Ref<T>> {
+  pub fn peek_back(&self) -> Option<Ref<'_, T>> {
     self.tail.as_ref().map(|node| Ref::map(node.borrow(), |node| &node.elem))
   }
 
-  pub fn peek_front_mut(&mut self) -> Option<RefMut<T>> {
+  pub fn peek_front_mut(&mut self) -> Option<RefMut<'_, T>> {
     self.head.as_ref().map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.elem))
   }
 
-  pub fn peek_back_mut(&mut self) -> Option<RefMut<T>> {
+  pub fn peek_back_mut(&mut self) -> Option<RefMut<'_, T>> {
     self.tail.as_ref().map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.elem))
   }
+}
 
-  pub fn into_iter(self) -> IntoIter<T> {
-    IntoIter(self)
+// 按下标访问，下标从 head 开始数，从离它近的一端走过去。
+// 第 i 个节点的 RefCell 只能经由前一个节点的 Ref 拿到，借出的 Ref 活不过这次遍历，
+// 所以 get/get_mut 接收闭包，元素只在闭包里可见；也就没有 Index/IndexMut（它们要求返回 &T）
+impl<T> List<T> {
+  fn node(&self, index: usize) -> Option<Rc<RefCell<Node<T>>>> {
+    if index >= self.len {
+      return None;
+    }
+    if index < self.len - index {
+      let mut node = self.head.clone()?;
+      for _ in 0..index {
+        let next = node.borrow().next.clone()?;
+        node = next;
+      }
+      Some(node)
+    } else {
+      let mut node = self.tail.clone()?;
+      for _ in index + 1..self.len {
+        let prev = node.borrow().prev.clone()?;
+        node = prev;
+      }
+      Some(node)
+    }
+  }
+
+  pub fn get<R>(&self, index: usize, f: impl FnOnce(&T) -> R) -> Option<R> {
+    self.node(index).map(|node| f(&node.borrow().elem))
+  }
+
+  pub fn get_mut<R>(&mut self, index: usize, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+    self.node(index).map(|node| f(&mut node.borrow_mut().elem))
+  }
+
+  // 新元素放到第 index 个位置上，index 可以等于 len
+  pub fn insert(&mut self, index: usize, elem: T) {
+    assert!(index <= self.len, "insertion index (is {index}) should be <= len (is {})", self.len);
+    if index == 0 {
+      return self.push_front(elem);
+    }
+    let after = self.node(index - 1).unwrap();
+    self.insert_node_after(&after, Node::new(elem));
+  }
+
+  pub fn remove(&mut self, index: usize) -> Option<T> {
+    let node = self.node(index)?;
+    self.unlink_node(&node);
+    Some(Rc::try_unwrap(node).ok().unwrap().into_inner().elem)
+  }
+
+  pub fn swap(&mut self, i: usize, j: usize) {
+    assert!(i < self.len && j < self.len, "swap index out of bounds: the len is {}", self.len);
+    if i == j {
+      return;
+    }
+    let (a, b) = (self.node(i).unwrap(), self.node(j).unwrap());
+    std::mem::swap(&mut a.borrow_mut().elem, &mut b.borrow_mut().elem);
+  }
+}
+
+// 借用失败时报告是哪个节点：index 是从 head 数的位置，address 和 debug_viz 里节点的地址一致，
+// mutable 表示失败的是可变借用（节点已经被借出去了）还是共享借用（节点正在被可变借用）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorrowError {
+  pub index: usize,
+  pub address: usize,
+  pub mutable: bool,
+}
+
+impl fmt::Display for BorrowError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.mutable {
+      write!(f, "node {} at {:#x} is already borrowed", self.index, self.address)
+    } else {
+      write!(f, "node {} at {:#x} is already mutably borrowed", self.index, self.address)
+    }
+  }
+}
+
+impl std::error::Error for BorrowError {}
+
+// 闭包版本的 peek，Ref/RefMut 在闭包返回时就释放了，调用者不会不小心一直拿着它。
+//
+// try_ 开头的版本在节点已经被借出去时返回 BorrowError 而不是 panic。
+// 公开的接口里可变借用都要 &mut self，冲突主要来自 crate 里直接持有节点的结构（比如 cache）
+impl<T> List<T> {
+  pub fn with_front<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+    self.peek_front().map(|elem| f(&elem))
+  }
+
+  pub fn with_back<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+    self.peek_back().map(|elem| f(&elem))
+  }
+
+  pub fn with_front_mut<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+    self.peek_front_mut().map(|mut elem| f(&mut elem))
+  }
+
+  pub fn with_back_mut<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+    self.peek_back_mut().map(|mut elem| f(&mut elem))
+  }
+
+  fn try_borrow<'a>(cell: &'a RefCell<Node<T>>, index: usize) -> Result<Ref<'a, T>, BorrowError> {
+    cell.try_borrow()
+      .map(|node| Ref::map(node, |node| &node.elem))
+      .map_err(|_| BorrowError { index, address: cell as *const _ as usize, mutable: false })
+  }
+
+  fn try_borrow_mut<'a>(cell: &'a RefCell<Node<T>>, index: usize) -> Result<RefMut<'a, T>, BorrowError> {
+    cell.try_borrow_mut()
+      .map(|node| RefMut::map(node, |node| &mut node.elem))
+      .map_err(|_| BorrowError { index, address: cell as *const _ as usize, mutable: true })
+  }
+
+  pub fn try_peek_front(&self) -> Result<Option<Ref<'_, T>>, BorrowError> {
+    self.head.as_deref().map(|cell| Self::try_borrow(cell, 0)).transpose()
+  }
+
+  pub fn try_peek_back(&self) -> Result<Option<Ref<'_, T>>, BorrowError> {
+    self.tail.as_deref().map(|cell| Self::try_borrow(cell, self.len - 1)).transpose()
+  }
+
+  pub fn try_peek_front_mut(&mut self) -> Result<Option<RefMut<'_, T>>, BorrowError> {
+    self.head.as_deref().map(|cell| Self::try_borrow_mut(cell, 0)).transpose()
+  }
+
+  pub fn try_peek_back_mut(&mut self) -> Result<Option<RefMut<'_, T>>, BorrowError> {
+    self.tail.as_deref().map(|cell| Self::try_borrow_mut(cell, self.len - 1)).transpose()
+  }
+}
+
+// 指向链表里某个节点的句柄，和 timer_wheel 的 TimerHandle 一样持有节点的 Weak。
+// 节点只被链表持有，pop/remove 之后或者链表被 drop 之后句柄就失效了
+pub struct NodeHandle<T> {
+  node: Weak<RefCell<Node<T>>>,
+  list: u64,
+}
+
+impl<T> Clone for NodeHandle<T> {
+  fn clone(&self) -> Self {
+    NodeHandle { node: self.node.clone(), list: self.list }
+  }
+}
+
+impl<T> Debug for NodeHandle<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("NodeHandle").field("list", &self.list).field("alive", &self.is_alive()).finish()
+  }
+}
+
+impl<T> NodeHandle<T> {
+  // 节点还在链表里
+  pub fn is_alive(&self) -> bool {
+    self.node.strong_count() > 0
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandleError {
+  // 节点已经被移除，或者链表已经被 drop
+  Dead,
+  // 句柄是别的链表发出来的
+  ForeignList,
+}
+
+impl fmt::Display for HandleError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      HandleError::Dead => f.write_str("node handle refers to a removed node"),
+      HandleError::ForeignList => f.write_str("node handle belongs to another list"),
+    }
+  }
+}
+
+impl std::error::Error for HandleError {}
+
+// 通过句柄 O(1) 地访问、移动和删除节点。
+// 按下标的 remove 已经占了名字，这里的版本带 by_handle 后缀
+impl<T> List<T> {
+  fn handle(&self, node: &Rc<RefCell<Node<T>>>) -> NodeHandle<T> {
+    NodeHandle { node: Rc::downgrade(node), list: self.id }
+  }
+
+  fn resolve(&self, handle: &NodeHandle<T>) -> Result<Rc<RefCell<Node<T>>>, HandleError> {
+    if handle.list != self.id {
+      return Err(HandleError::ForeignList);
+    }
+    handle.node.upgrade().ok_or(HandleError::Dead)
+  }
+
+  pub fn push_front_handle(&mut self, elem: T) -> NodeHandle<T> {
+    self.push_front(elem);
+    self.handle(self.head.as_ref().unwrap())
+  }
+
+  pub fn push_back_handle(&mut self, elem: T) -> NodeHandle<T> {
+    self.push_back(elem);
+    self.handle(self.tail.as_ref().unwrap())
+  }
+
+  // 和 get 一样：upgrade 出来的 Rc 是临时的，元素只能通过闭包访问。
+  // get/remove 已经是按下标的版本，Rust 没有重载，所以按句柄的叫 with_handle/remove_by_handle
+  pub fn with_handle<R>(&self, handle: &NodeHandle<T>, f: impl FnOnce(&T) -> R) -> Result<R, HandleError> {
+    Ok(f(&self.resolve(handle)?.borrow().elem))
+  }
+
+  pub fn with_handle_mut<R>(&mut self, handle: &NodeHandle<T>, f: impl FnOnce(&mut T) -> R) -> Result<R, HandleError> {
+    Ok(f(&mut self.resolve(handle)?.borrow_mut().elem))
+  }
+
+  // 删除之后这个句柄和它的所有副本都会失效
+  pub fn remove_by_handle(&mut self, handle: &NodeHandle<T>) -> Result<T, HandleError> {
+    let node = self.resolve(handle)?;
+    self.unlink_node(&node);
+    Ok(Rc::try_unwrap(node).ok().unwrap().into_inner().elem)
+  }
+
+  // 句柄在移动之后仍然有效
+  pub fn move_to_front(&mut self, handle: &NodeHandle<T>) -> Result<(), HandleError> {
+    let node = self.resolve(handle)?;
+    if !self.head.as_ref().is_some_and(|head| Rc::ptr_eq(head, &node)) {
+      self.unlink_node(&node);
+      self.push_front_node(node);
+    }
+    Ok(())
+  }
+
+  pub fn move_to_back(&mut self, handle: &NodeHandle<T>) -> Result<(), HandleError> {
+    let node = self.resolve(handle)?;
+    if !self.tail.as_ref().is_some_and(|tail| Rc::ptr_eq(tail, &node)) {
+      // node 不是 tail，摘下来之后链表里还有别的节点
+      self.unlink_node(&node);
+      let tail = self.tail.clone().unwrap();
+      self.insert_node_after(&tail, node);
+    }
+    Ok(())
+  }
+
+  // 句柄无效时 elem 会被直接 drop
+  pub fn insert_after(&mut self, handle: &NodeHandle<T>, elem: T) -> Result<NodeHandle<T>, HandleError> {
+    let after = self.resolve(handle)?;
+    let node = Node::new(elem);
+    let new_handle = self.handle(&node);
+    self.insert_node_after(&after, node);
+    Ok(new_handle)
+  }
+}
+
+// 节点级别的操作，给 crate 里复用 deque 节点的结构（比如 cache）用
+// 调用者要保证传进来的节点不在任何链表里（push）或者就在这个链表里（unlink）。
+// 节点可以这样在链表之间移动，所以这些结构不要和 NodeHandle 混用
+impl<T> List<T> {
+  pub(crate) fn front_node(&self) -> Option<&Rc<RefCell<Node<T>>>> {
+    self.head.as_ref()
+  }
+
+  pub(crate) fn back_node(&self) -> Option<&Rc<RefCell<Node<T>>>> {
+    self.tail.as_ref()
+  }
+
+  pub(crate) fn push_front_node(&mut self, node: Rc<RefCell<Node<T>>>) {
+    match self.head.take() {
+      Some(old_head) => {
+        old_head.borrow_mut().prev = Some(node.clone());
+        node.borrow_mut().next = Some(old_head);
+      },
+      None => {
+        self.tail = Some(node.clone());
+      }
+    }
+    self.head = Some(node);
+    self.len += 1;
+  }
+
+  // 把 node 插到 after 的后面，after 必须在这个链表里
+  pub(crate) fn insert_node_after(&mut self, after: &Rc<RefCell<Node<T>>>, node: Rc<RefCell<Node<T>>>) {
+    let next = after.borrow_mut().next.take();
+    match &next {
+      Some(next) => next.borrow_mut().prev = Some(node.clone()),
+      None => self.tail = Some(node.clone()),
+    }
+    {
+      let mut inner = node.borrow_mut();
+      inner.prev = Some(after.clone());
+      inner.next = next;
+    }
+    after.borrow_mut().next = Some(node);
+    self.len += 1;
+  }
+
+  // O(1) 地把 node 从链表中摘下来，node 仍然由调用者持有
+  pub(crate) fn unlink_node(&mut self, node: &Rc<RefCell<Node<T>>>) {
+    let (prev, next) = {
+      let mut inner = node.borrow_mut();
+      (inner.prev.take(), inner.next.take())
+    };
+    match &prev {
+      Some(prev) => prev.borrow_mut().next = next.clone(),
+      None => self.head = next.clone(),
+    }
+    match next {
+      Some(next) => next.borrow_mut().prev = prev,
+      None => self.tail = prev,
+    }
+    self.len -= 1;
+  }
+}
+
+// 链表结构的体检报告，下标是节点从 head 沿 next 数的位置，
+// 只能从 tail 沿 prev 走到的节点排在后面
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AuditReport {
+  // 链表记录的长度
+  pub len: usize,
+  // 从 head 沿 next 走到的节点数
+  pub forward_len: usize,
+  // 从 tail 沿 prev 走到的节点数
+  pub backward_len: usize,
+  // next 链上回到了走过的节点
+  pub next_cycle: bool,
+  // prev 链上回到了走过的节点
+  pub prev_cycle: bool,
+  // prev 或 next 指向自己的节点
+  pub self_loops: Vec<usize>,
+  // next 指向的节点的 prev 没有指回来的位置
+  pub broken_links: Vec<usize>,
+  // 从 tail 走不到的节点数
+  pub unreachable_from_tail: usize,
+  // 只从 tail 走得到、从 head 走不到的节点数
+  pub unreachable_from_head: usize,
+  // (下标, 多出来的强引用数)：强引用比链表内部指向它的指针多，说明有外部持有者或者泄漏
+  pub extra_refs: Vec<(usize, usize)>,
+  // 体检时正被可变借用的节点，读不到它的 prev/next，遍历在这里停下。
+  // 这时报告是不完整的，也不再检查 extra_refs（内部指针数数不全）
+  pub borrowed: Vec<usize>,
+}
+
+impl AuditReport {
+  pub fn is_healthy(&self) -> bool {
+    !self.next_cycle
+      && !self.prev_cycle
+      && self.self_loops.is_empty()
+      && self.broken_links.is_empty()
+      && self.unreachable_from_tail == 0
+      && self.unreachable_from_head == 0
+      && self.extra_refs.is_empty()
+      && self.borrowed.is_empty()
+      && self.forward_len == self.len
+      && self.backward_len == self.len
+  }
+}
+
+// 用 Weak 记住链表里所有的节点，链表没了之后还活着的节点就是泄漏的
+pub struct LeakProbe<T> {
+  nodes: Vec<Weak<RefCell<Node<T>>>>,
+}
+
+impl<T> LeakProbe<T> {
+  pub fn alive(&self) -> usize {
+    self.nodes.iter().filter(|node| node.strong_count() > 0).count()
+  }
+}
+
+// 诊断用的 API。双向链表本身的 prev/next 就构成 Rc 环，要靠 Drop 逐个拆开，
+// 一旦结构被破坏（自环、prev 没指回来、链表被 mem::forget），节点就可能永远释放不掉。
+// persistent_stack/persistent_queue 虽然也基于 Rc，但节点创建后 next 不再改变，只能指向更早的节点，
+// 不会成环；强引用计数大于 1 是版本之间共享节点的正常结果，所以这套 API 只给 deque 提供。
+// 遍历只用 try_borrow，节点正被借用时记在 borrowed 里，不会 panic
+impl<T> List<T> {
+  // 从 head 和 tail 两头各走一遍，对照每个节点的引用计数
+  pub fn audit(&self) -> AuditReport {
+    let mut report = AuditReport { len: self.len, ..AuditReport::default() };
+    let nodes = self.collect_nodes(&mut report);
+    let address = |node: &Rc<RefCell<Node<T>>>| Rc::as_ptr(node);
+    let index: HashMap<_, _> = nodes.iter().enumerate().map(|(i, node)| (address(node), i)).collect();
+    let complete = report.borrowed.is_empty();
+
+    // 链表内部指向每个节点的指针数
+    let mut internal = vec![0; nodes.len()];
+    let mut count = |link: &Link<T>| {
+      if let Some(i) = link.as_ref().and_then(|node| index.get(&address(node))) {
+        internal[*i] += 1;
+      }
+    };
+    count(&self.head);
+    count(&self.tail);
+    for node in &nodes {
+      if let Ok(inner) = node.try_borrow() {
+        count(&inner.prev);
+        count(&inner.next);
+      }
+    }
+
+    for (i, node) in nodes.iter().enumerate() {
+      let Ok(inner) = node.try_borrow() else {
+        continue;
+      };
+      let points_to_self = |link: &Link<T>| link.as_ref().is_some_and(|other| Rc::ptr_eq(other, node));
+      if points_to_self(&inner.prev) || points_to_self(&inner.next) {
+        report.self_loops.push(i);
+      }
+      // next 正被借用时看不到它的 prev，不算断链
+      if let Some(Ok(next)) = inner.next.as_ref().map(|next| next.try_borrow()) {
+        if !next.prev.as_ref().is_some_and(|prev| Rc::ptr_eq(prev, node)) {
+          report.broken_links.push(i);
+        }
+      }
+      // nodes 里的这一份不算
+      let strong = Rc::strong_count(node) - 1;
+      if complete && strong > internal[i] {
+        report.extra_refs.push((i, strong - internal[i]));
+      }
+    }
+    report
+  }
+
+  // next 链或 prev 链上有环（包括自环）。这样的链表遍历不会结束，Drop 也拆不干净
+  pub fn detect_cycles(&self) -> bool {
+    let report = self.audit();
+    report.next_cycle || report.prev_cycle || !report.self_loops.is_empty()
+  }
+
+  // 之后可以用 LeakProbe::alive 检查这些节点有没有被释放
+  pub fn watch(&self) -> LeakProbe<T> {
+    let mut report = AuditReport::default();
+    let nodes = self.collect_nodes(&mut report);
+    LeakProbe { nodes: nodes.iter().map(Rc::downgrade).collect() }
+  }
+
+  // 收集从 head 和 tail 能走到的所有节点，每个节点只出现一次。正被借用的节点收进来，但不再往后走
+  fn collect_nodes(&self, report: &mut AuditReport) -> Vec<Rc<RefCell<Node<T>>>> {
+    let mut nodes = Vec::new();
+    let mut seen = HashSet::new();
+    let mut current = self.head.clone();
+    while let Some(node) = current {
+      if !seen.insert(Rc::as_ptr(&node)) {
+        report.next_cycle = true;
+        break;
+      }
+      current = match node.try_borrow() {
+        Ok(inner) => inner.next.clone(),
+        Err(_) => {
+          report.borrowed.push(nodes.len());
+          None
+        }
+      };
+      nodes.push(node);
+    }
+    report.forward_len = nodes.len();
+
+    let mut backward = HashSet::new();
+    let mut current = self.tail.clone();
+    while let Some(node) = current {
+      if !backward.insert(Rc::as_ptr(&node)) {
+        report.prev_cycle = true;
+        break;
+      }
+      let next = node.try_borrow().map(|inner| inner.prev.clone());
+      if seen.insert(Rc::as_ptr(&node)) {
+        if next.is_err() {
+          report.borrowed.push(nodes.len());
+        }
+        nodes.push(node);
+      }
+      current = next.unwrap_or(None);
+    }
+    report.backward_len = backward.len();
+    report.unreachable_from_head = nodes.len() - report.forward_len;
+    report.unreachable_from_tail = nodes.len() - backward.len();
+    nodes
   }
 }
 
+// 这些 trait 都沿着 next 循环实现。节点在 RefCell 里，每次只借用一个节点
+impl<T> List<T> {
+  fn nodes(&self) -> impl Iterator<Item = Rc<RefCell<Node<T>>>> {
+    std::iter::successors(self.head.clone(), |node| RefCell::borrow(node).next.clone())
+  }
+}
+
+// 当作栈用时在 back 一端进出
+impl<T> traits::Stack<T> for List<T> {
+  type Peek<'a> = Ref<'a, T> where T: 'a;
+
+  fn push(&mut self, elem: T) {
+    List::push_back(self, elem)
+  }
+
+  fn pop(&mut self) -> Option<T> {
+    List::pop_back(self)
+  }
+
+  fn peek(&self) -> Option<Ref<'_, T>> {
+    List::peek_back(self)
+  }
+
+  fn len(&self) -> usize {
+    List::len(self)
+  }
+}
+
+impl<T> traits::Queue<T> for List<T> {
+  type Peek<'a> = Ref<'a, T> where T: 'a;
+
+  fn push_back(&mut self, elem: T) {
+    List::push_back(self, elem)
+  }
+
+  fn pop_front(&mut self) -> Option<T> {
+    List::pop_front(self)
+  }
+
+  fn peek_front(&self) -> Option<Ref<'_, T>> {
+    List::peek_front(self)
+  }
+
+  fn len(&self) -> usize {
+    List::len(self)
+  }
+}
+
+impl<T> traits::Deque<T> for List<T> {
+  fn push_front(&mut self, elem: T) {
+    List::push_front(self, elem)
+  }
+
+  fn pop_back(&mut self) -> Option<T> {
+    List::pop_back(self)
+  }
+
+  fn peek_back(&self) -> Option<Ref<'_, T>> {
+    List::peek_back(self)
+  }
+}
+
+impl<T: Debug> Debug for List<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut list = f.debug_list();
+    for node in self.nodes() {
+      list.entry(&RefCell::borrow(&node).elem);
+    }
+    list.finish()
+  }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+  fn eq(&self, other: &Self) -> bool {
+    if self.len != other.len {
+      return false;
+    }
+    let (mut a, mut b) = (self.nodes(), other.nodes());
+    loop {
+      match (a.next(), b.next()) {
+        (None, None) => return true,
+        (Some(x), Some(y)) if RefCell::borrow(&x).elem == RefCell::borrow(&y).elem => {},
+        _ => return false,
+      }
+    }
+  }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: Hash> Hash for List<T> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    state.write_usize(self.len);
+    for node in self.nodes() {
+      RefCell::borrow(&node).elem.hash(state);
+    }
+  }
+}
+
+impl<T: Clone> Clone for List<T> {
+  fn clone(&self) -> Self {
+    let mut list = List::new();
+    for node in self.nodes() {
+      list.push_back(RefCell::borrow(&node).elem.clone());
+    }
+    list
+  }
+}
+
+impl<T> Default for List<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// 元素的 drop panic 时也要接着 pop，
+// 不然剩下的节点之间 prev/next 互相持有，整段链表都会泄漏
 impl<T> Drop for List<T> {
   fn drop(&mut self) {
-    while self.pop_front().is_some() {}
+    util::pop_all(self, List::pop_front);
   }
 }
 
 pub struct IntoIter<T>(List<T>);
 
+impl<T> IntoIterator for List<T> {
+  type Item = T;
+  type IntoIter = IntoIter<T>;
+
+  fn into_iter(self) -> IntoIter<T> {
+    IntoIter(self)
+  }
+}
+
 impl<T> Iterator for IntoIter<T> {
   type Item = T;
 
   fn next(&mut self) -> Option<Self::Item> {
     self.0.pop_front()
   }
-}
-
-pub struct Iter<'a, T>(Option<Ref<'a, Node<T>>>);
 
-impl<T> List<T> {
-  pub fn iter(&self) -> Iter<'_, T> {
-    Iter(self.head.as_ref().map(|node| node.borrow()))
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.0.len, Some(self.0.len))
   }
 }
 
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+// deque 没有 iter。下面是基于 Iter<'a, T>(Option<Ref<'a, Node<T>>>) 试过的写法，
+// 下一个节点的 Ref 只能从当前节点的 Ref 里借出来，活不过当前这一步，怎么写都过不了借用检查。
+// 要遍历就用 into_iter，或者按下标用 get
 // impl<'a, T> Iterator for Iter<'a, T> {
 //   type Item = Ref<'a, T>;
 
@@ -196,6 +834,34 @@ impl<T> List<T> {
   // }
 // }
 
+// 按 next 遍历，同时画出每个节点的 prev，节点重复出现（有环）时停下
+// 正在被可变借用的节点读不到内容，画成 <borrowed> 并停下
+impl<T: Debug> Visualize for List<T> {
+  fn visualize(&self, name: &str, graph: &mut Graph) {
+    let address = |node: &Rc<RefCell<Node<T>>>| Rc::as_ptr(node) as usize;
+    graph.add_root(name, self.head.as_ref().map(address), self.tail.as_ref().map(address));
+    let mut current = self.head.clone();
+    while let Some(node) = current.take() {
+      // 减去 current 自己持有的那一个引用
+      let strong = Some(Rc::strong_count(&node) - 1);
+      let Ok(inner) = node.try_borrow() else {
+        graph.add_node(address(&node), "<borrowed>".to_string(), strong);
+        break;
+      };
+      if !graph.add_node(address(&node), format!("{:?}", inner.elem), strong) {
+        break;
+      }
+      if let Some(prev) = &inner.prev {
+        graph.add_edge(address(&node), address(prev), EdgeKind::Prev);
+      }
+      if let Some(next) = &inner.next {
+        graph.add_edge(address(&node), address(next), EdgeKind::Next);
+      }
+      current = inner.next.clone();
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -222,6 +888,31 @@ mod tests {
     assert_eq!(list.pop_back(), Some(3));
   }
 
+  #[test]
+  fn push_back() {
+    let mut list = List::new();
+    list.push_back(1);
+    list.push_back(2);
+    list.push_back(3);
+
+    // 新尾节点的 prev 必须指向旧尾节点，而不是它自己
+    let report = list.audit();
+    assert!(report.is_healthy(), "{:?}", report);
+    assert!(report.self_loops.is_empty());
+
+    assert_eq!(list.pop_back(), Some(3));
+    assert_eq!(list.pop_back(), Some(2));
+    assert_eq!(list.pop_back(), Some(1));
+    assert_eq!(list.pop_back(), None);
+
+    // 链表释放后节点也都跟着释放
+    list.push_back(4);
+    list.push_back(5);
+    let probe = list.watch();
+    drop(list);
+    assert_eq!(probe.alive(), 0);
+  }
+
   #[test]
   fn peek() {
     let mut list = List::new();
@@ -241,4 +932,287 @@ mod tests {
     *back.unwrap() += 1;
     assert_eq!(&*list.peek_back().unwrap(), &2);
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn index() {
+    let mut list = List::new();
+    for i in 0..10 {
+      list.push_back(i);
+    }
+    // 前半段从 head 走，后半段从 tail 走
+    assert_eq!((0..11).map(|i| list.get(i, |elem| *elem)).collect::<Vec<_>>(),
+               (0..10).map(Some).chain([None]).collect::<Vec<_>>());
+
+    list.insert(0, -1);
+    list.insert(11, 10);
+    list.insert(3, 100);
+    list.insert(9, 200);
+    assert_eq!(format!("{:?}", list), "[-1, 0, 1, 100, 2, 3, 4, 5, 6, 200, 7, 8, 9, 10]");
+    assert!(list.audit().is_healthy());
+
+    list.get_mut(1, |elem| *elem = 50);
+    assert_eq!(list.get_mut(12, |elem| std::mem::replace(elem, 10)), Some(9));
+    assert_eq!(list.get_mut(14, |elem| *elem += 1), None);
+    list.swap(1, 12);
+    list.swap(3, 3);
+    assert_eq!((list.get(1, |elem| *elem), list.get(12, |elem| *elem)), (Some(10), Some(50)));
+
+    assert_eq!(list.remove(9), Some(200));
+    assert_eq!(list.remove(3), Some(100));
+    assert_eq!(list.remove(0), Some(-1));
+    assert_eq!(list.remove(10), Some(10));
+    assert_eq!(list.remove(10), None);
+    assert_eq!(format!("{:?}", list), "[10, 1, 2, 3, 4, 5, 6, 7, 8, 50]");
+    assert_eq!(list.audit().len, 10);
+    assert!(list.audit().is_healthy());
+  }
+
+  #[test]
+  #[should_panic(expected = "insertion index (is 2) should be <= len (is 1)")]
+  fn insert_out_of_bounds() {
+    let mut list = List::new();
+    list.push_back(1);
+    list.insert(2, 2);
+  }
+
+  #[test]
+  fn with_closures() {
+    let mut list = List::new();
+    assert_eq!(list.with_front(|elem: &String| elem.len()), None);
+    assert_eq!(list.with_back_mut(|elem: &mut String| elem.clear()), None);
+
+    list.push_back(String::from("ab"));
+    list.push_back(String::from("cde"));
+    assert_eq!(list.with_front(String::len), Some(2));
+    assert_eq!(list.with_back(|elem| elem.to_uppercase()).as_deref(), Some("CDE"));
+    list.with_front_mut(|elem| elem.push('!'));
+    // 闭包返回之后没有留下借用，可以接着修改链表
+    let back = list.with_back_mut(std::mem::take).unwrap();
+    list.push_back(back);
+    assert_eq!(format!("{:?}", list), r#"["ab!", "", "cde"]"#);
+  }
+
+  #[test]
+  fn try_peek() {
+    let mut list = List::new();
+    assert!(matches!(list.try_peek_front(), Ok(None)));
+    assert!(matches!(list.try_peek_back_mut(), Ok(None)));
+    for i in 0..5 {
+      list.push_back(i);
+    }
+    assert_eq!(list.try_peek_back().unwrap().map(|elem| *elem), Some(4));
+    *list.try_peek_front_mut().unwrap().unwrap() = 10;
+    assert_eq!(list.try_peek_front().unwrap().map(|elem| *elem), Some(10));
+
+    // 模拟 crate 里别的结构正持有节点的借用
+    let head = list.front_node().unwrap().clone();
+    let address = Rc::as_ptr(&head) as usize;
+    {
+      let shared = head.borrow();
+      let err = list.try_peek_front_mut().err().unwrap();
+      assert_eq!(err, BorrowError { index: 0, address, mutable: true });
+      assert_eq!(err.to_string(), format!("node 0 at {address:#x} is already borrowed"));
+      assert_eq!(list.try_peek_front().unwrap().map(|elem| *elem), Some(10));
+      drop(shared);
+    }
+    {
+      let _exclusive = head.borrow_mut();
+      let err = list.try_peek_front().err().unwrap();
+      assert!(!err.mutable);
+      assert_eq!(err.to_string(), format!("node 0 at {address:#x} is already mutably borrowed"));
+      // 另一端不受影响
+      assert_eq!(list.try_peek_back_mut().unwrap().map(|elem| *elem), Some(4));
+    }
+    let tail = list.back_node().unwrap().clone();
+    let _exclusive = tail.borrow_mut();
+    let err: Box<dyn std::error::Error> = Box::new(list.try_peek_back().err().unwrap());
+    assert!(err.to_string().starts_with("node 4 at"));
+  }
+
+  #[test]
+  fn handles() {
+    let mut list = List::new();
+    let b = list.push_back_handle("b");
+    let c = list.push_back_handle("c");
+    let a = list.push_front_handle("a");
+    let d = list.insert_after(&c, "d").unwrap();
+    let bb = list.insert_after(&b, "bb").unwrap();
+    assert_eq!(format!("{:?}", list), r#"["a", "b", "bb", "c", "d"]"#);
+    assert_eq!(list.with_handle(&bb, |elem| *elem), Ok("bb"));
+
+    // 移动之后句柄仍然指向同一个元素
+    list.move_to_front(&c).unwrap();
+    list.move_to_front(&c).unwrap();
+    list.move_to_back(&a).unwrap();
+    list.with_handle_mut(&a, |elem| *elem = "A").unwrap();
+    assert_eq!(format!("{:?}", list), r#"["c", "b", "bb", "d", "A"]"#);
+    assert!(list.audit().is_healthy());
+
+    assert_eq!(list.remove_by_handle(&bb), Ok("bb"));
+    assert_eq!(list.remove_by_handle(&c), Ok("c"));
+    assert!(!bb.is_alive() && d.is_alive());
+    assert_eq!(list.remove_by_handle(&bb.clone()), Err(HandleError::Dead));
+    assert_eq!(list.with_handle(&c, |elem| *elem), Err(HandleError::Dead));
+    assert_eq!(list.insert_after(&c, "e").err(), Some(HandleError::Dead));
+    assert_eq!(format!("{:?}", list), r#"["b", "d", "A"]"#);
+
+    // pop 出去的节点、别的链表的句柄
+    assert_eq!(list.pop_back(), Some("A"));
+    assert_eq!(list.move_to_front(&a), Err(HandleError::Dead));
+    let mut other = List::new();
+    let foreign = other.push_back_handle("x");
+    assert_eq!(list.move_to_back(&foreign), Err(HandleError::ForeignList));
+    assert_eq!(list.with_handle_mut(&foreign, |elem| *elem), Err(HandleError::ForeignList));
+    assert_eq!(HandleError::ForeignList.to_string(), "node handle belongs to another list");
+    // clone 出来的是另一个链表
+    assert_eq!(list.clone().remove_by_handle(&d), Err(HandleError::ForeignList));
+    assert_eq!(list.len(), 2);
+    assert!(list.audit().is_healthy());
+
+    // 链表 drop 之后所有句柄都失效，不会因为句柄而泄漏
+    let probe = list.watch();
+    drop(list);
+    assert!(!b.is_alive() && !d.is_alive());
+    assert_eq!(probe.alive(), 0);
+    assert!(format!("{:?}", b).contains("alive: false"));
+  }
+
+  #[test]
+  fn len() {
+    let mut list = List::new();
+    assert!(list.is_empty());
+    list.push_back(2);
+    list.push_front(1);
+    list.push_back(3);
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.pop_back(), Some(3));
+    assert_eq!(list.pop_front(), Some(1));
+    assert_eq!(list.len(), 1);
+
+    // 节点级别的操作也要维护长度
+    list.push_front_node(Node::new(0));
+    let front = list.front_node().unwrap().clone();
+    list.insert_node_after(&front, Node::new(5));
+    assert_eq!(list.len(), 3);
+    list.unlink_node(&front);
+    assert_eq!(list.len(), 2);
+    assert!(list.audit().is_healthy());
+
+    let mut into_iter = list.into_iter();
+    assert_eq!(into_iter.len(), 2);
+    into_iter.next();
+    assert_eq!(into_iter.size_hint(), (1, Some(1)));
+  }
+
+  #[test]
+  fn audit_healthy() {
+    let mut list = List::new();
+    assert!(list.audit().is_healthy());
+    for i in 0..5 {
+      list.push_front(i);
+    }
+    let report = list.audit();
+    assert!(report.is_healthy(), "{:?}", report);
+    assert_eq!((report.forward_len, report.backward_len), (5, 5));
+    assert!(!list.detect_cycles());
+
+    // 外部持有的节点会被报告出来
+    let held = list.front_node().unwrap().clone();
+    assert_eq!(list.audit().extra_refs, vec![(0, 1)]);
+    drop(held);
+
+    let probe = list.watch();
+    assert_eq!(probe.alive(), 5);
+    drop(list);
+    assert_eq!(probe.alive(), 0);
+  }
+
+  #[test]
+  fn audit_borrowed_node() {
+    let mut list = List::new();
+    for i in 0..5 {
+      list.push_back(i);
+    }
+    let second = list.front_node().unwrap().borrow().next_node().unwrap().clone();
+    let guard = second.borrow_mut();
+    let report = list.audit();
+    assert_eq!(report.borrowed, vec![1]);
+    assert_eq!((report.forward_len, report.backward_len), (2, 4));
+    assert!(report.broken_links.is_empty() && report.extra_refs.is_empty());
+    assert!(!report.is_healthy());
+    assert!(!list.detect_cycles());
+    assert_eq!(list.watch().alive(), 5);
+
+    drop(guard);
+    drop(second);
+    assert!(list.audit().is_healthy());
+  }
+
+  #[test]
+  #[cfg_attr(miri, ignore)] // 故意泄漏
+  fn forget_leaks() {
+    let mut list = List::new();
+    for i in 0..3 {
+      list.push_front(i);
+    }
+    let probe = list.watch();
+    std::mem::forget(list);
+    assert_eq!(probe.alive(), 3);
+  }
+
+  #[test]
+  #[cfg_attr(miri, ignore)] // 故意泄漏
+  fn self_loop() {
+    let mut list = List::new();
+    list.push_back(1);
+    list.push_back(2);
+
+    // 早先的 push_back 把新节点的 prev 指向了它自己，这里手动构造出同样的结构
+    let tail = list.tail.clone().unwrap();
+    tail.borrow_mut().prev = Some(tail.clone());
+    drop(tail);
+
+    let report = list.audit();
+    assert!(list.detect_cycles());
+    assert!(report.prev_cycle);
+    assert_eq!(report.self_loops, vec![1]);
+    assert_eq!(report.broken_links, vec![0]);
+    assert_eq!(report.backward_len, 1);
+    assert_eq!(report.unreachable_from_tail, 1);
+
+    // 节点一旦脱离链表，自环会让它永远活着
+    let probe = list.watch();
+    list.head.as_ref().unwrap().borrow_mut().next = None;
+    list.tail = None;
+    drop(list);
+    assert_eq!(probe.alive(), 1);
+  }
+
+  #[test]
+  fn visualize_stops_at_cycle() {
+    let mut list = List::new();
+    for i in 1..=3 {
+      list.push_back(i);
+    }
+    // 手动让尾节点的 next 指回头节点
+    let head = list.head.clone().unwrap();
+    let tail = list.tail.clone().unwrap();
+    tail.borrow_mut().next = Some(head.clone());
+    let (head_address, tail_address) = (Rc::as_ptr(&head) as usize, Rc::as_ptr(&tail) as usize);
+
+    let ascii = list.to_ascii();
+    assert!(ascii.contains(&format!("(cycle to {:#x})", head_address)), "{}", ascii);
+    assert_eq!(ascii.matches("[1 @").count(), 1);
+    assert_eq!(ascii.lines().count(), 1);
+
+    let mut graph = Graph::new();
+    graph.add("deque", &list);
+    assert_eq!(graph.node_count(), 3);
+    let dot = graph.to_dot();
+    assert_eq!(dot.matches("[label=\"1\\n").count(), 1);
+    assert_eq!(dot.matches(&format!("n{:x} -> n{:x};", tail_address, head_address)).count(), 1);
+
+    // 拆掉环，链表才能正常释放
+    tail.borrow_mut().next = None;
+  }
+}
@@ -0,0 +1,677 @@
+// 2-3 finger tree（Hinze & Paterson）实现的不可变序列，按元素个数做 measure
+// 两端各有 1~4 个节点的 digit，中间是一棵元素为 2-3 节点的 finger tree，越往里节点越大
+// 两端的 push/pop 均摊 O(1)，concat、split_at、get 都是 O(log n)
+// 和 persistent_stack 一样，所有节点都用 Rc 共享，每个操作返回新版本，旧版本不受影响
+//
+// Rust 不好表达 FingerTree<Node<T>> 这种多态递归（泛型会无限单态化），
+// 所以各层共用同一个 Node<T>，叶子和分支的层次关系靠构造保证
+
+use std::ops::Index;
+use std::rc::Rc;
+
+use crate::traits::PersistentStack;
+
+enum Node<T> {
+  Leaf(T),
+  Branch2(usize, Rc<Node<T>>, Rc<Node<T>>),
+  Branch3(usize, Rc<Node<T>>, Rc<Node<T>>, Rc<Node<T>>),
+}
+
+type NodeRef<T> = Rc<Node<T>>;
+// 1 到 4 个节点
+type Digit<T> = Vec<NodeRef<T>>;
+
+enum Tree<T> {
+  Empty,
+  Single(NodeRef<T>),
+  Deep { size: usize, prefix: Digit<T>, middle: Rc<Tree<T>>, suffix: Digit<T> },
+}
+
+pub struct FingerTree<T> {
+  tree: Tree<T>,
+}
+
+impl<T> Node<T> {
+  fn size(&self) -> usize {
+    match self {
+      Node::Leaf(_) => 1,
+      Node::Branch2(size, ..) | Node::Branch3(size, ..) => *size,
+    }
+  }
+
+  fn branch2(a: NodeRef<T>, b: NodeRef<T>) -> NodeRef<T> {
+    Rc::new(Node::Branch2(a.size() + b.size(), a, b))
+  }
+
+  fn branch3(a: NodeRef<T>, b: NodeRef<T>, c: NodeRef<T>) -> NodeRef<T> {
+    Rc::new(Node::Branch3(a.size() + b.size() + c.size(), a, b, c))
+  }
+
+  fn to_digit(&self) -> Digit<T> {
+    match self {
+      Node::Leaf(_) => unreachable!("leaves never appear in the middle tree"),
+      Node::Branch2(_, a, b) => vec![a.clone(), b.clone()],
+      Node::Branch3(_, a, b, c) => vec![a.clone(), b.clone(), c.clone()],
+    }
+  }
+
+  fn leaf(&self) -> &T {
+    match self {
+      Node::Leaf(elem) => elem,
+      _ => unreachable!("the outermost level only holds leaves"),
+    }
+  }
+
+  // 在节点内部按下标往下找到叶子
+  fn get(&self, mut index: usize) -> &T {
+    let mut node = self;
+    loop {
+      node = match node {
+        Node::Leaf(elem) => return elem,
+        Node::Branch2(_, a, b) => pick([a, b], &mut index),
+        Node::Branch3(_, a, b, c) => pick([a, b, c], &mut index),
+      };
+    }
+  }
+}
+
+// 找到包含 index 的那个节点，index 被改写成节点内部的下标
+fn pick<'a, T: 'a>(nodes: impl IntoIterator<Item = &'a NodeRef<T>>, index: &mut usize) -> &'a Node<T> {
+  for node in nodes {
+    let size = node.size();
+    if *index < size {
+      return node;
+    }
+    *index -= size;
+  }
+  unreachable!("index out of bounds")
+}
+
+fn digit_size<T>(digit: &[NodeRef<T>]) -> usize {
+  digit.iter().map(|node| node.size()).sum()
+}
+
+// 拆成 (左边, 包含 index 的节点, 右边)
+fn split_digit<T>(digit: &[NodeRef<T>], mut index: usize) -> (&[NodeRef<T>], NodeRef<T>, &[NodeRef<T>]) {
+  for (i, node) in digit.iter().enumerate() {
+    let size = node.size();
+    if index < size {
+      return (&digit[..i], node.clone(), &digit[i + 1..]);
+    }
+    index -= size;
+  }
+  unreachable!("index out of bounds")
+}
+
+// concat 时把两棵树中间夹着的 2~12 个节点重新打包成 2-3 节点
+fn nodes<T>(items: &[NodeRef<T>]) -> Vec<NodeRef<T>> {
+  let mut result = Vec::new();
+  let mut rest = items;
+  loop {
+    match rest {
+      [a, b] => {
+        result.push(Node::branch2(a.clone(), b.clone()));
+        return result;
+      },
+      [a, b, c] => {
+        result.push(Node::branch3(a.clone(), b.clone(), c.clone()));
+        return result;
+      },
+      [a, b, c, d] => {
+        result.push(Node::branch2(a.clone(), b.clone()));
+        result.push(Node::branch2(c.clone(), d.clone()));
+        return result;
+      },
+      [a, b, c, ..] => {
+        result.push(Node::branch3(a.clone(), b.clone(), c.clone()));
+        rest = &rest[3..];
+      },
+      _ => unreachable!("at least two nodes sit between two deep trees"),
+    }
+  }
+}
+
+// 只复制 digit 和中间树的 Rc，节点都是共享的
+impl<T> Clone for Tree<T> {
+  fn clone(&self) -> Self {
+    match self {
+      Tree::Empty => Tree::Empty,
+      Tree::Single(node) => Tree::Single(node.clone()),
+      Tree::Deep { size, prefix, middle, suffix } => Tree::Deep {
+        size: *size,
+        prefix: prefix.clone(),
+        middle: middle.clone(),
+        suffix: suffix.clone(),
+      },
+    }
+  }
+}
+
+impl<T> Tree<T> {
+  fn size(&self) -> usize {
+    match self {
+      Tree::Empty => 0,
+      Tree::Single(node) => node.size(),
+      Tree::Deep { size, .. } => *size,
+    }
+  }
+
+  fn deep(prefix: Digit<T>, middle: Rc<Tree<T>>, suffix: Digit<T>) -> Self {
+    let size = digit_size(&prefix) + middle.size() + digit_size(&suffix);
+    Tree::Deep { size, prefix, middle, suffix }
+  }
+
+  fn from_digit(digit: &[NodeRef<T>]) -> Self {
+    digit.iter().fold(Tree::Empty, |tree, node| tree.push_back(node.clone()))
+  }
+
+  fn push_front(&self, node: NodeRef<T>) -> Self {
+    match self {
+      Tree::Empty => Tree::Single(node),
+      Tree::Single(other) => Tree::deep(vec![node], Rc::new(Tree::Empty), vec![other.clone()]),
+      Tree::Deep { prefix, middle, suffix, .. } => {
+        if let [a, b, c, d] = &prefix[..] {
+          // digit 满了，把其中三个打包成一个节点推进中间的树
+          let middle = middle.push_front(Node::branch3(b.clone(), c.clone(), d.clone()));
+          Tree::deep(vec![node, a.clone()], Rc::new(middle), suffix.clone())
+        } else {
+          let mut new_prefix = Vec::with_capacity(prefix.len() + 1);
+          new_prefix.push(node);
+          new_prefix.extend(prefix.iter().cloned());
+          Tree::deep(new_prefix, middle.clone(), suffix.clone())
+        }
+      }
+    }
+  }
+
+  fn push_back(&self, node: NodeRef<T>) -> Self {
+    match self {
+      Tree::Empty => Tree::Single(node),
+      Tree::Single(other) => Tree::deep(vec![other.clone()], Rc::new(Tree::Empty), vec![node]),
+      Tree::Deep { prefix, middle, suffix, .. } => {
+        if let [a, b, c, d] = &suffix[..] {
+          let middle = middle.push_back(Node::branch3(a.clone(), b.clone(), c.clone()));
+          Tree::deep(prefix.clone(), Rc::new(middle), vec![d.clone(), node])
+        } else {
+          let mut new_suffix = suffix.clone();
+          new_suffix.push(node);
+          Tree::deep(prefix.clone(), middle.clone(), new_suffix)
+        }
+      }
+    }
+  }
+
+  fn view_front(&self) -> Option<(NodeRef<T>, Self)> {
+    match self {
+      Tree::Empty => None,
+      Tree::Single(node) => Some((node.clone(), Tree::Empty)),
+      Tree::Deep { prefix, middle, suffix, .. } => Some((prefix[0].clone(), Tree::deep_l(&prefix[1..], middle, suffix))),
+    }
+  }
+
+  fn view_back(&self) -> Option<(Self, NodeRef<T>)> {
+    match self {
+      Tree::Empty => None,
+      Tree::Single(node) => Some((Tree::Empty, node.clone())),
+      Tree::Deep { prefix, middle, suffix, .. } => {
+        let last = suffix.len() - 1;
+        Some((Tree::deep_r(prefix, middle, &suffix[..last]), suffix[last].clone()))
+      }
+    }
+  }
+
+  // prefix 可能为空，为空时从中间的树借一个节点展开成新的 prefix
+  fn deep_l(prefix: &[NodeRef<T>], middle: &Rc<Tree<T>>, suffix: &[NodeRef<T>]) -> Self {
+    if !prefix.is_empty() {
+      return Tree::deep(prefix.to_vec(), middle.clone(), suffix.to_vec());
+    }
+    match middle.view_front() {
+      None => Tree::from_digit(suffix),
+      Some((node, rest)) => Tree::deep(node.to_digit(), Rc::new(rest), suffix.to_vec()),
+    }
+  }
+
+  fn deep_r(prefix: &[NodeRef<T>], middle: &Rc<Tree<T>>, suffix: &[NodeRef<T>]) -> Self {
+    if !suffix.is_empty() {
+      return Tree::deep(prefix.to_vec(), middle.clone(), suffix.to_vec());
+    }
+    match middle.view_back() {
+      None => Tree::from_digit(prefix),
+      Some((rest, node)) => Tree::deep(prefix.to_vec(), Rc::new(rest), node.to_digit()),
+    }
+  }
+
+  // left ++ middle ++ right
+  fn app3(left: &Tree<T>, middle: &[NodeRef<T>], right: &Tree<T>) -> Self {
+    match (left, right) {
+      (Tree::Empty, _) => middle.iter().rev().fold(right.clone(), |tree, node| tree.push_front(node.clone())),
+      (_, Tree::Empty) => middle.iter().fold(left.clone(), |tree, node| tree.push_back(node.clone())),
+      (Tree::Single(node), _) => Tree::app3(&Tree::Empty, middle, right).push_front(node.clone()),
+      (_, Tree::Single(node)) => Tree::app3(left, middle, &Tree::Empty).push_back(node.clone()),
+      (
+        Tree::Deep { prefix: left_prefix, middle: left_middle, suffix: left_suffix, .. },
+        Tree::Deep { prefix: right_prefix, middle: right_middle, suffix: right_suffix, .. },
+      ) => {
+        let mut items = left_suffix.clone();
+        items.extend(middle.iter().cloned());
+        items.extend(right_prefix.iter().cloned());
+        let middle = Tree::app3(left_middle, &nodes(&items), right_middle);
+        Tree::deep(left_prefix.clone(), Rc::new(middle), right_suffix.clone())
+      }
+    }
+  }
+
+  // 要求 index < size，返回 (左边, 包含 index 的节点, 右边)
+  fn split(&self, index: usize) -> (Self, NodeRef<T>, Self) {
+    match self {
+      Tree::Empty => unreachable!("split on an empty tree"),
+      Tree::Single(node) => (Tree::Empty, node.clone(), Tree::Empty),
+      Tree::Deep { prefix, middle, suffix, .. } => {
+        let prefix_size = digit_size(prefix);
+        if index < prefix_size {
+          let (left, node, right) = split_digit(prefix, index);
+          return (Tree::from_digit(left), node, Tree::deep_l(right, middle, suffix));
+        }
+
+        let index = index - prefix_size;
+        let middle_size = middle.size();
+        if index < middle_size {
+          let (middle_left, branch, middle_right) = middle.split(index);
+          let index = index - middle_left.size();
+          let digit = branch.to_digit();
+          let (left, node, right) = split_digit(&digit, index);
+          return (
+            Tree::deep_r(prefix, &Rc::new(middle_left), left),
+            node,
+            Tree::deep_l(right, &Rc::new(middle_right), suffix),
+          );
+        }
+
+        let (left, node, right) = split_digit(suffix, index - middle_size);
+        (Tree::deep_r(prefix, middle, left), node, Tree::from_digit(right))
+      }
+    }
+  }
+
+  // 要求 index < size
+  fn get(&self, index: usize) -> &T {
+    let mut tree = self;
+    let mut index = index;
+    loop {
+      match tree {
+        Tree::Empty => unreachable!("get on an empty tree"),
+        Tree::Single(node) => return node.get(index),
+        Tree::Deep { prefix, middle, suffix, .. } => {
+          let prefix_size = digit_size(prefix);
+          if index < prefix_size {
+            return pick(prefix, &mut index).get(index);
+          }
+          index -= prefix_size;
+          if index < middle.size() {
+            tree = middle;
+            continue;
+          }
+          index -= middle.size();
+          return pick(suffix, &mut index).get(index);
+        }
+      }
+    }
+  }
+}
+
+impl<T> FingerTree<T> {
+  pub fn new() -> Self {
+    FingerTree { tree: Tree::Empty }
+  }
+
+  pub fn len(&self) -> usize {
+    self.tree.size()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    matches!(self.tree, Tree::Empty)
+  }
+
+  pub fn push_front(&self, elem: T) -> Self {
+    FingerTree { tree: self.tree.push_front(Rc::new(Node::Leaf(elem))) }
+  }
+
+  pub fn push_back(&self, elem: T) -> Self {
+    FingerTree { tree: self.tree.push_back(Rc::new(Node::Leaf(elem))) }
+  }
+
+  pub fn front(&self) -> Option<&T> {
+    match &self.tree {
+      Tree::Empty => None,
+      Tree::Single(node) => Some(node.leaf()),
+      Tree::Deep { prefix, .. } => Some(prefix[0].leaf()),
+    }
+  }
+
+  pub fn back(&self) -> Option<&T> {
+    match &self.tree {
+      Tree::Empty => None,
+      Tree::Single(node) => Some(node.leaf()),
+      Tree::Deep { suffix, .. } => Some(suffix[suffix.len() - 1].leaf()),
+    }
+  }
+
+  // 返回队首元素和去掉它之后的新版本
+  pub fn pop_front(&self) -> Option<(&T, Self)> {
+    let front = self.front()?;
+    let (_, rest) = self.tree.view_front()?;
+    Some((front, FingerTree { tree: rest }))
+  }
+
+  pub fn pop_back(&self) -> Option<(&T, Self)> {
+    let back = self.back()?;
+    let (rest, _) = self.tree.view_back()?;
+    Some((back, FingerTree { tree: rest }))
+  }
+
+  pub fn get(&self, index: usize) -> Option<&T> {
+    if index < self.len() {
+      Some(self.tree.get(index))
+    } else {
+      None
+    }
+  }
+
+  pub fn concat(&self, other: &Self) -> Self {
+    FingerTree { tree: Tree::app3(&self.tree, &[], &other.tree) }
+  }
+
+  // 左边是前 index 个元素，index 超过长度时右边为空
+  pub fn split_at(&self, index: usize) -> (Self, Self) {
+    if index == 0 {
+      return (FingerTree::new(), self.clone());
+    }
+    if index >= self.len() {
+      return (self.clone(), FingerTree::new());
+    }
+    let (left, node, right) = self.tree.split(index);
+    (FingerTree { tree: left }, FingerTree { tree: right.push_front(node) })
+  }
+
+  pub fn iter(&self) -> Iter<'_, T> {
+    Iter { stack: vec![Item::Tree(&self.tree)] }
+  }
+}
+
+// 当作栈用时在 front 一端进出
+impl<T> PersistentStack<T> for FingerTree<T> {
+  fn push(&self, elem: T) -> Self {
+    self.push_front(elem)
+  }
+
+  fn pop(&self) -> Option<(&T, Self)> {
+    self.pop_front()
+  }
+
+  fn peek(&self) -> Option<&T> {
+    self.front()
+  }
+
+  fn len(&self) -> usize {
+    FingerTree::len(self)
+  }
+}
+
+// 节点都是共享的，没有 IndexMut
+impl<T> Index<usize> for FingerTree<T> {
+  type Output = T;
+
+  fn index(&self, index: usize) -> &T {
+    let len = self.len();
+    self.get(index).unwrap_or_else(|| panic!("index out of bounds: the len is {len} but the index is {index}"))
+  }
+}
+
+impl<T> Clone for FingerTree<T> {
+  fn clone(&self) -> Self {
+    FingerTree { tree: self.tree.clone() }
+  }
+}
+
+impl<T> Default for FingerTree<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T> FromIterator<T> for FingerTree<T> {
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    let tree = iter.into_iter().fold(Tree::Empty, |tree, elem| tree.push_back(Rc::new(Node::Leaf(elem))));
+    FingerTree { tree }
+  }
+}
+
+enum Item<'a, T> {
+  Tree(&'a Tree<T>),
+  Node(&'a Node<T>),
+}
+
+// 用显式的栈做中序遍历，栈顶是下一个要展开的部分
+pub struct Iter<'a, T> {
+  stack: Vec<Item<'a, T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+  type Item = &'a T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      match self.stack.pop()? {
+        Item::Node(Node::Leaf(elem)) => return Some(elem),
+        Item::Node(Node::Branch2(_, a, b)) => {
+          self.stack.push(Item::Node(b));
+          self.stack.push(Item::Node(a));
+        },
+        Item::Node(Node::Branch3(_, a, b, c)) => {
+          self.stack.push(Item::Node(c));
+          self.stack.push(Item::Node(b));
+          self.stack.push(Item::Node(a));
+        },
+        Item::Tree(Tree::Empty) => {},
+        Item::Tree(Tree::Single(node)) => self.stack.push(Item::Node(node)),
+        Item::Tree(Tree::Deep { prefix, middle, suffix, .. }) => {
+          self.stack.extend(suffix.iter().rev().map(|node| Item::Node(node)));
+          self.stack.push(Item::Tree(middle));
+          self.stack.extend(prefix.iter().rev().map(|node| Item::Node(node)));
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // 检查结构不变量：digit 里有 1~4 个节点，缓存的 size 正确，同一层的节点深度相同
+  fn check<T>(tree: &FingerTree<T>) {
+    fn node_depth<T>(node: &Node<T>) -> (usize, usize) {
+      match node {
+        Node::Leaf(_) => (0, 1),
+        Node::Branch2(size, a, b) => {
+          let (da, sa) = node_depth(a);
+          let (db, sb) = node_depth(b);
+          assert_eq!(da, db);
+          assert_eq!(*size, sa + sb);
+          (da + 1, *size)
+        },
+        Node::Branch3(size, a, b, c) => {
+          let (da, sa) = node_depth(a);
+          let (db, sb) = node_depth(b);
+          let (dc, sc) = node_depth(c);
+          assert!(da == db && db == dc);
+          assert_eq!(*size, sa + sb + sc);
+          (da + 1, *size)
+        },
+      }
+    }
+
+    fn tree_size<T>(tree: &Tree<T>, depth: usize) -> usize {
+      match tree {
+        Tree::Empty => 0,
+        Tree::Single(node) => {
+          assert_eq!(node_depth(node).0, depth);
+          node.size()
+        },
+        Tree::Deep { size, prefix, middle, suffix } => {
+          assert!((1..=4).contains(&prefix.len()));
+          assert!((1..=4).contains(&suffix.len()));
+          for node in prefix.iter().chain(suffix) {
+            assert_eq!(node_depth(node).0, depth);
+          }
+          let total = digit_size(prefix) + tree_size(middle, depth + 1) + digit_size(suffix);
+          assert_eq!(*size, total);
+          total
+        }
+      }
+    }
+
+    assert_eq!(tree_size(&tree.tree, 0), tree.len());
+    assert_eq!(tree.iter().count(), tree.len());
+  }
+
+  fn to_vec<T: Clone>(tree: &FingerTree<T>) -> Vec<T> {
+    tree.iter().cloned().collect()
+  }
+
+  #[test]
+  fn basic() {
+    let tree = FingerTree::new();
+    assert_eq!(tree.front(), None);
+    let tree = tree.push_back(2).push_back(3).push_front(1);
+    assert_eq!(tree.len(), 3);
+    assert_eq!(tree.front(), Some(&1));
+    assert_eq!(tree.back(), Some(&3));
+    assert_eq!(to_vec(&tree), vec![1, 2, 3]);
+
+    let (front, rest) = tree.pop_front().unwrap();
+    assert_eq!(front, &1);
+    let (back, rest) = rest.pop_back().unwrap();
+    assert_eq!(back, &3);
+    assert_eq!(to_vec(&rest), vec![2]);
+    assert_eq!(to_vec(&tree), vec![1, 2, 3]);
+  }
+
+  #[test]
+  fn get() {
+    let tree: FingerTree<_> = (0..1000).collect();
+    check(&tree);
+    for i in 0..1000 {
+      assert_eq!(tree.get(i), Some(&i));
+    }
+    assert_eq!(tree.get(1000), None);
+    assert_eq!(tree[999], 999);
+  }
+
+  #[test]
+  #[should_panic(expected = "index out of bounds: the len is 3 but the index is 3")]
+  fn index_out_of_bounds() {
+    let tree: FingerTree<_> = (0..3).collect();
+    let _ = tree[3];
+  }
+
+  #[test]
+  fn concat_and_split() {
+    // Miri 下少跑一些
+    let (ns, ms): (&[usize], &[usize]) = if cfg!(miri) { (&[0, 1, 5, 30], &[0, 3, 27]) } else { (&[0, 1, 2, 5, 9, 30, 100], &[0, 1, 3, 8, 27, 100]) };
+    for &n in ns {
+      for &m in ms {
+        let left: FingerTree<_> = (0..n).collect();
+        let right: FingerTree<_> = (n..n + m).collect();
+        let joined = left.concat(&right);
+        check(&joined);
+        assert_eq!(to_vec(&joined), (0..n + m).collect::<Vec<_>>());
+
+        for i in 0..=n + m {
+          let (a, b) = joined.split_at(i);
+          check(&a);
+          check(&b);
+          assert_eq!(to_vec(&a), (0..i).collect::<Vec<_>>());
+          assert_eq!(to_vec(&b), (i..n + m).collect::<Vec<_>>());
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn structural_sharing() {
+    let tree: FingerTree<_> = (0..100).collect();
+    let pushed = tree.push_back(100).push_front(-1);
+    let (left, right) = tree.split_at(40);
+    let joined = right.concat(&left);
+
+    // 叶子节点在所有版本之间共享，取到的是同一个元素
+    for i in 0..100 {
+      assert!(std::ptr::eq(tree.get(i).unwrap(), pushed.get(i + 1).unwrap()));
+      assert!(std::ptr::eq(tree.get(i).unwrap(), joined.get((i + 60) % 100).unwrap()));
+    }
+    assert!(std::ptr::eq(tree.get(39).unwrap(), left.back().unwrap()));
+    assert!(std::ptr::eq(tree.get(40).unwrap(), right.front().unwrap()));
+
+    // 旧版本不受影响
+    assert_eq!(to_vec(&tree), (0..100).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn persistent_model() {
+    let mut x = 0x2545_f491_4f6c_dd1du64;
+    let mut rand = move |n: usize| {
+      x ^= x << 13;
+      x ^= x >> 7;
+      x ^= x << 17;
+      (x % n as u64) as usize
+    };
+
+    let mut versions = vec![(FingerTree::new(), Vec::new())];
+    for step in 0..if cfg!(miri) { 200 } else { 2000 } {
+      let (tree, model) = versions[rand(versions.len())].clone();
+      let (tree, model) = match rand(6) {
+        0 => (tree.push_front(step), [vec![step], model].concat()),
+        1 => (tree.push_back(step), [model, vec![step]].concat()),
+        2 => match tree.pop_front() {
+          Some((front, rest)) => {
+            assert_eq!(Some(front), model.first());
+            (rest, model[1..].to_vec())
+          },
+          None => (tree, model),
+        },
+        3 => match tree.pop_back() {
+          Some((back, rest)) => {
+            assert_eq!(Some(back), model.last());
+            (rest, model[..model.len() - 1].to_vec())
+          },
+          None => (tree, model),
+        },
+        4 => {
+          let (other, other_model) = versions[rand(versions.len())].clone();
+          (tree.concat(&other), [model, other_model].concat())
+        },
+        _ => {
+          let at = rand(model.len() + 1);
+          let (left, right) = tree.split_at(at);
+          if rand(2) == 0 {
+            (left, model[..at].to_vec())
+          } else {
+            (right, model[at..].to_vec())
+          }
+        }
+      };
+
+      check(&tree);
+      assert_eq!(to_vec(&tree), model);
+      if !model.is_empty() {
+        let i = rand(model.len());
+        assert_eq!(tree.get(i), Some(&model[i]));
+      }
+      // 控制长度，避免反复 concat 之后越来越大
+      if model.len() < 2000 {
+        versions.push((tree, model));
+      }
+    }
+  }
+}
@@ -1,5 +1,21 @@
 pub mod stack_bad;
 pub mod stack_ok;
 pub mod persistent_stack;
+pub mod persistent_queue;
+pub mod finger_tree;
 pub mod deque;
 pub mod unsafe_deque_ok;
+pub mod bounded_queue;
+pub mod channel;
+pub mod priority_queue;
+pub mod cache;
+pub mod timer_wheel;
+pub mod debug_viz;
+pub mod traits;
+mod util;
+#[cfg(test)]
+mod conformance;
+#[cfg(test)]
+mod test_utils;
+#[cfg(feature = "async")]
+pub mod async_queue;
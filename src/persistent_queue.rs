@@ -0,0 +1,512 @@
+// 用两个 persistent_stack 拼成的不可变队列
+// front 按出队顺序存放，rear 按入队的逆序存放，每次操作都返回新版本，旧版本保持不变并和新版本共享节点
+//
+// PersistentQueue 是 banker's queue：rear 比 front 长时把 rear 反转接到 front 后面，均摊 O(1)
+// 但在持久化的场景下，同一个旧版本可以反复触发同一次昂贵的反转，所以还提供了 RealTimeQueue：
+// Hood-Melville 队列把反转拆成很多小步，每次操作只推进常数步（惰性地重新平衡），最坏情况 O(1)
+//
+// 节点里的元素需要被复制到新节点中（反转、追加），所以大部分操作要求 T: Clone
+
+use std::fmt::Debug;
+use std::ops::Index;
+
+use crate::debug_viz::{Graph, Visualize};
+use crate::persistent_stack::List;
+use crate::traits;
+
+fn reversed<T: Clone>(list: &List<T>) -> List<T> {
+  list.iter().fold(List::new(), |acc, elem| acc.prepend(elem.clone()))
+}
+
+pub struct PersistentQueue<T> {
+  front: List<T>,
+  front_len: usize,
+  rear: List<T>,
+  rear_len: usize,
+}
+
+impl<T> PersistentQueue<T> {
+  pub fn new() -> Self {
+    PersistentQueue { front: List::new(), front_len: 0, rear: List::new(), rear_len: 0 }
+  }
+
+  pub fn peek_front(&self) -> Option<&T> {
+    self.front.head()
+  }
+
+  pub fn len(&self) -> usize {
+    self.front_len + self.rear_len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn iter(&self) -> Iter<'_, T> {
+    Iter { front: self.front.iter(), rear: Some(self.rear.iter()), rear_rev: Vec::new() }
+  }
+
+  // 下标从队首开始数，落在 rear 里时从 rear 的头（队尾）倒着数
+  pub fn get(&self, index: usize) -> Option<&T> {
+    if index < self.front_len {
+      self.front.get(index)
+    } else if index < self.len() {
+      self.rear.get(self.len() - 1 - index)
+    } else {
+      None
+    }
+  }
+}
+
+// 每个版本都和别的版本共享节点，没有 IndexMut
+impl<T> Index<usize> for PersistentQueue<T> {
+  type Output = T;
+
+  fn index(&self, index: usize) -> &T {
+    let len = self.len();
+    self.get(index).unwrap_or_else(|| panic!("index out of bounds: the len is {len} but the index is {index}"))
+  }
+}
+
+impl<T: Clone> PersistentQueue<T> {
+  pub fn push_back(&self, elem: T) -> Self {
+    Self::check(PersistentQueue {
+      front: self.front.clone(),
+      front_len: self.front_len,
+      rear: self.rear.prepend(elem),
+      rear_len: self.rear_len + 1,
+    })
+  }
+
+  // 返回队首元素和去掉它之后的新版本
+  pub fn pop_front(&self) -> Option<(&T, Self)> {
+    let elem = self.front.head()?;
+    let rest = Self::check(PersistentQueue {
+      front: self.front.tail(),
+      front_len: self.front_len - 1,
+      rear: self.rear.clone(),
+      rear_len: self.rear_len,
+    });
+    Some((elem, rest))
+  }
+
+  // 保持 rear_len <= front_len，这样只要队列非空 front 就非空
+  fn check(queue: Self) -> Self {
+    if queue.rear_len <= queue.front_len {
+      return queue;
+    }
+    // front ++ reverse(rear)，front 的节点要重新复制一遍
+    let front: Vec<&T> = queue.front.iter().collect();
+    let front = front.into_iter().rev().fold(reversed(&queue.rear), |acc, elem| acc.prepend(elem.clone()));
+    PersistentQueue { front, front_len: queue.front_len + queue.rear_len, rear: List::new(), rear_len: 0 }
+  }
+}
+
+impl<T> Clone for PersistentQueue<T> {
+  fn clone(&self) -> Self {
+    PersistentQueue {
+      front: self.front.clone(),
+      front_len: self.front_len,
+      rear: self.rear.clone(),
+      rear_len: self.rear_len,
+    }
+  }
+}
+
+impl<T> Default for PersistentQueue<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// front 和 rear 各是一个入口，画进同一个 Graph 时多个版本共享的节点只出现一次
+impl<T: Debug> Visualize for PersistentQueue<T> {
+  fn visualize(&self, name: &str, graph: &mut Graph) {
+    self.front.visualize(&format!("{}.front", name), graph);
+    self.rear.visualize(&format!("{}.rear", name), graph);
+  }
+}
+
+impl<T: Clone> traits::PersistentQueue<T> for PersistentQueue<T> {
+  fn push_back(&self, elem: T) -> Self {
+    PersistentQueue::push_back(self, elem)
+  }
+
+  fn pop_front(&self) -> Option<(&T, Self)> {
+    PersistentQueue::pop_front(self)
+  }
+
+  fn peek_front(&self) -> Option<&T> {
+    PersistentQueue::peek_front(self)
+  }
+
+  fn len(&self) -> usize {
+    PersistentQueue::len(self)
+  }
+}
+
+pub struct Iter<'a, T> {
+  front: crate::persistent_stack::Iter<'a, T>,
+  // rear 是逆序的，front 走完之后才把它收集到 rear_rev 里，再从尾部往前取
+  // 只看前面几个元素的调用（比如 take、find）不会分配
+  rear: Option<crate::persistent_stack::Iter<'a, T>>,
+  rear_rev: Vec<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+  type Item = &'a T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if let Some(elem) = self.front.next() {
+      return Some(elem);
+    }
+    if let Some(rear) = self.rear.take() {
+      self.rear_rev = rear.collect();
+    }
+    self.rear_rev.pop()
+  }
+}
+
+// Hood-Melville 队列的重新平衡状态机（Okasaki, Purely Functional Data Structures, 8.2.1）
+// Reversing: 同时反转 f 和 r，ok 记录 f' 里还有多少个元素是有效的
+// Appending: 把 f' 逐个放到 r' 前面
+// Done: 得到新的 front
+enum Rotation<T> {
+  Idle,
+  Reversing { ok: usize, f: List<T>, f_rev: List<T>, r: List<T>, r_rev: List<T> },
+  Appending { ok: usize, f_rev: List<T>, r_rev: List<T> },
+  Done(List<T>),
+}
+
+impl<T> Clone for Rotation<T> {
+  fn clone(&self) -> Self {
+    match self {
+      Rotation::Idle => Rotation::Idle,
+      Rotation::Reversing { ok, f, f_rev, r, r_rev } => Rotation::Reversing {
+        ok: *ok,
+        f: f.clone(),
+        f_rev: f_rev.clone(),
+        r: r.clone(),
+        r_rev: r_rev.clone(),
+      },
+      Rotation::Appending { ok, f_rev, r_rev } => Rotation::Appending { ok: *ok, f_rev: f_rev.clone(), r_rev: r_rev.clone() },
+      Rotation::Done(list) => Rotation::Done(list.clone()),
+    }
+  }
+}
+
+impl<T: Clone> Rotation<T> {
+  // 推进一步
+  fn exec(&self) -> Self {
+    match self {
+      Rotation::Reversing { ok, f, f_rev, r, r_rev } => match (f.head(), r.head()) {
+        (Some(x), Some(y)) => Rotation::Reversing {
+          ok: ok + 1,
+          f: f.tail(),
+          f_rev: f_rev.prepend(x.clone()),
+          r: r.tail(),
+          r_rev: r_rev.prepend(y.clone()),
+        },
+        // f 已经反转完，r 正好剩最后一个
+        (None, Some(y)) => Rotation::Appending { ok: *ok, f_rev: f_rev.clone(), r_rev: r_rev.prepend(y.clone()) },
+        _ => unreachable!("rotation starts with |r| = |f| + 1"),
+      },
+      Rotation::Appending { ok: 0, r_rev, .. } => Rotation::Done(r_rev.clone()),
+      Rotation::Appending { ok, f_rev, r_rev } => Rotation::Appending {
+        ok: ok - 1,
+        f_rev: f_rev.tail(),
+        r_rev: r_rev.prepend(f_rev.head().unwrap().clone()),
+      },
+      state => state.clone(),
+    }
+  }
+
+  // 旧的 front 被弹出了一个元素，f' 中的有效元素少一个
+  fn invalidate(&self) -> Self {
+    match self {
+      Rotation::Reversing { ok, f, f_rev, r, r_rev } => Rotation::Reversing {
+        ok: ok - 1,
+        f: f.clone(),
+        f_rev: f_rev.clone(),
+        r: r.clone(),
+        r_rev: r_rev.clone(),
+      },
+      Rotation::Appending { ok: 0, r_rev, .. } => Rotation::Done(r_rev.tail()),
+      Rotation::Appending { ok, f_rev, r_rev } => Rotation::Appending { ok: ok - 1, f_rev: f_rev.clone(), r_rev: r_rev.clone() },
+      state => state.clone(),
+    }
+  }
+}
+
+pub struct RealTimeQueue<T> {
+  front_len: usize,
+  front: List<T>,
+  state: Rotation<T>,
+  rear_len: usize,
+  rear: List<T>,
+}
+
+impl<T> RealTimeQueue<T> {
+  pub fn new() -> Self {
+    RealTimeQueue { front_len: 0, front: List::new(), state: Rotation::Idle, rear_len: 0, rear: List::new() }
+  }
+
+  pub fn peek_front(&self) -> Option<&T> {
+    self.front.head()
+  }
+
+  pub fn len(&self) -> usize {
+    self.front_len + self.rear_len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.front_len == 0
+  }
+}
+
+impl<T: Clone> RealTimeQueue<T> {
+  pub fn push_back(&self, elem: T) -> Self {
+    Self::check(RealTimeQueue {
+      front_len: self.front_len,
+      front: self.front.clone(),
+      state: self.state.clone(),
+      rear_len: self.rear_len + 1,
+      rear: self.rear.prepend(elem),
+    })
+  }
+
+  pub fn pop_front(&self) -> Option<(&T, Self)> {
+    let elem = self.front.head()?;
+    let rest = Self::check(RealTimeQueue {
+      front_len: self.front_len - 1,
+      front: self.front.tail(),
+      state: self.state.invalidate(),
+      rear_len: self.rear_len,
+      rear: self.rear.clone(),
+    });
+    Some((elem, rest))
+  }
+
+  // 每次操作推进两步，保证下一次重新平衡开始之前这一次已经完成
+  fn exec2(mut queue: Self) -> Self {
+    match queue.state.exec().exec() {
+      Rotation::Done(front) => {
+        queue.front = front;
+        queue.state = Rotation::Idle;
+      },
+      state => queue.state = state,
+    }
+    queue
+  }
+
+  fn check(queue: Self) -> Self {
+    if queue.rear_len <= queue.front_len {
+      return Self::exec2(queue);
+    }
+    let state = Rotation::Reversing { ok: 0, f: queue.front.clone(), f_rev: List::new(), r: queue.rear, r_rev: List::new() };
+    Self::exec2(RealTimeQueue {
+      front_len: queue.front_len + queue.rear_len,
+      front: queue.front,
+      state,
+      rear_len: 0,
+      rear: List::new(),
+    })
+  }
+}
+
+impl<T> Clone for RealTimeQueue<T> {
+  fn clone(&self) -> Self {
+    RealTimeQueue {
+      front_len: self.front_len,
+      front: self.front.clone(),
+      state: self.state.clone(),
+      rear_len: self.rear_len,
+      rear: self.rear.clone(),
+    }
+  }
+}
+
+impl<T> Default for RealTimeQueue<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// 只画 front 和 rear，进行到一半的反转里的中间链表不画
+impl<T: Debug> Visualize for RealTimeQueue<T> {
+  fn visualize(&self, name: &str, graph: &mut Graph) {
+    self.front.visualize(&format!("{}.front", name), graph);
+    self.rear.visualize(&format!("{}.rear", name), graph);
+  }
+}
+
+impl<T: Clone> traits::PersistentQueue<T> for RealTimeQueue<T> {
+  fn push_back(&self, elem: T) -> Self {
+    RealTimeQueue::push_back(self, elem)
+  }
+
+  fn pop_front(&self) -> Option<(&T, Self)> {
+    RealTimeQueue::pop_front(self)
+  }
+
+  fn peek_front(&self) -> Option<&T> {
+    RealTimeQueue::peek_front(self)
+  }
+
+  fn len(&self) -> usize {
+    RealTimeQueue::len(self)
+  }
+}
+
+// 重新平衡的中间状态里元素分散在好几个列表中，所以按值迭代：复制一份队列不断出队
+pub struct IntoIter<T>(RealTimeQueue<T>);
+
+impl<T: Clone> IntoIterator for RealTimeQueue<T> {
+  type Item = T;
+  type IntoIter = IntoIter<T>;
+
+  fn into_iter(self) -> IntoIter<T> {
+    IntoIter(self)
+  }
+}
+
+impl<T: Clone> Iterator for IntoIter<T> {
+  type Item = T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let (elem, rest) = self.0.pop_front()?;
+    let elem = elem.clone();
+    self.0 = rest;
+    Some(elem)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::VecDeque;
+
+  #[test]
+  fn basic() {
+    let queue = PersistentQueue::new();
+    assert_eq!(queue.peek_front(), None);
+    let queue = queue.push_back(1).push_back(2).push_back(3);
+    assert_eq!(queue.len(), 3);
+    assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+
+    let (elem, rest) = queue.pop_front().unwrap();
+    assert_eq!(elem, &1);
+    let rest = rest.push_back(4);
+    assert_eq!(rest.iter().collect::<Vec<_>>(), vec![&2, &3, &4]);
+    // 旧版本不受影响
+    assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+  }
+
+  #[test]
+  fn iter_reverses_rear_lazily() {
+    use crate::test_utils::live_allocations;
+
+    // front 是 [1, 2, 3]，rear 是 [4]
+    let queue = PersistentQueue::new().push_back(1).push_back(2).push_back(3).push_back(4);
+    let mut iter = queue.iter();
+    let before = live_allocations();
+    assert!(iter.by_ref().take(3).eq([&1, &2, &3]));
+    assert_eq!(live_allocations(), before);
+    assert_eq!(iter.next(), Some(&4));
+    assert_eq!(live_allocations(), before + 1);
+    assert_eq!(iter.next(), None);
+  }
+
+  #[test]
+  fn index() {
+    // 1 2 3 在 front 里，4 5 在 rear 里
+    let queue = (1..=5).fold(PersistentQueue::new(), |queue, i| queue.push_back(i));
+    assert_eq!((queue.front_len, queue.rear_len), (3, 2));
+    assert_eq!((0..6).map(|i| queue.get(i)).collect::<Vec<_>>(), vec![Some(&1), Some(&2), Some(&3), Some(&4), Some(&5), None]);
+    assert_eq!(queue[4], 5);
+  }
+
+  #[test]
+  fn real_time_basic() {
+    let mut queue = RealTimeQueue::new();
+    for i in 0..10 {
+      queue = queue.push_back(i);
+    }
+    assert_eq!(queue.len(), 10);
+    let old = queue.clone();
+    for i in 0..5 {
+      let (elem, rest) = queue.pop_front().unwrap();
+      assert_eq!(*elem, i);
+      queue = rest.push_back(i + 10);
+    }
+    assert_eq!(queue.into_iter().collect::<Vec<_>>(), (5..15).collect::<Vec<_>>());
+    assert_eq!(old.into_iter().collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn structural_sharing() {
+    let queue = PersistentQueue::new().push_back(1).push_back(2).push_back(3).push_back(4);
+    // push_back 只动 rear，新旧版本的队首是同一个节点
+    let pushed = queue.push_back(5);
+    assert!(std::ptr::eq(queue.peek_front().unwrap(), pushed.peek_front().unwrap()));
+    // pop_front 之后剩下的 front 也是共享的
+    let (_, popped) = queue.pop_front().unwrap();
+    let (_, popped_again) = pushed.pop_front().unwrap();
+    assert!(std::ptr::eq(popped.peek_front().unwrap(), popped_again.peek_front().unwrap()));
+  }
+
+  // 在随机挑选的历史版本上执行操作，每个版本都和自己的 VecDeque 模型对比
+  #[test]
+  fn persistent_model() {
+    let mut x = 0x9e37_79b9_7f4a_7c15u64;
+    let mut rand = move |n: usize| {
+      x ^= x << 13;
+      x ^= x >> 7;
+      x ^= x << 17;
+      (x % n as u64) as usize
+    };
+
+    let mut banker = vec![(PersistentQueue::new(), VecDeque::new())];
+    let mut real_time = vec![(RealTimeQueue::new(), VecDeque::new())];
+    for step in 0..if cfg!(miri) { 300 } else { 3000 } {
+      let version = rand(banker.len());
+      let (queue, model) = &banker[version];
+      let (rt_queue, rt_model) = &real_time[version];
+      assert_eq!(model, rt_model);
+
+      let (next, next_rt, next_model) = if rand(3) == 0 {
+        match (queue.pop_front(), rt_queue.pop_front()) {
+          (Some((a, next)), Some((b, next_rt))) => {
+            assert_eq!(Some(a), model.front());
+            assert_eq!(a, b);
+            let mut next_model = model.clone();
+            next_model.pop_front();
+            (next, next_rt, next_model)
+          },
+          (None, None) => {
+            assert!(model.is_empty());
+            continue;
+          },
+          _ => panic!("banker's and real-time queues disagree"),
+        }
+      } else {
+        let mut next_model = model.clone();
+        next_model.push_back(step);
+        (queue.push_back(step), rt_queue.push_back(step), next_model)
+      };
+
+      assert_eq!(next.len(), next_model.len());
+      assert_eq!(next_rt.len(), next_model.len());
+      assert_eq!(next.peek_front(), next_model.front());
+      assert_eq!(next_rt.peek_front(), next_model.front());
+      assert!(next.iter().eq(next_model.iter()));
+      banker.push((next, next_model.clone()));
+      real_time.push((next_rt, next_model));
+    }
+
+    for ((queue, model), (rt_queue, _)) in banker.iter().zip(real_time) {
+      assert!(queue.iter().eq(model.iter()));
+      assert!(rt_queue.into_iter().eq(model.iter().copied()));
+    }
+  }
+}
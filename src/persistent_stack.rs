@@ -14,10 +14,23 @@
 //           |
 // list3 ->  X
 
-use std::rc::Rc;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::ops::{Index, IndexMut};
+use std::rc::{Rc, Weak};
 
+use crate::debug_viz::{EdgeKind, Graph, Visualize};
+use crate::traits::PersistentStack;
+use crate::util;
+
+// len 是从这个节点开始的链表长度。节点创建之后 next 不会再变，所以可以缓存在节点上，
+// 各个版本共享同一个节点时长度也是一样的
+#[derive(Clone)]
 pub struct Node<T> {
   elem: T,
+  len: usize,
   next: Link<T>
 }
 
@@ -36,6 +49,7 @@ impl<T> List<T> {
     List {
       head: Some(Rc::new(Node {
         elem, 
+        len: self.len() + 1,
         next: self.head.clone()
       }))
     }
@@ -49,18 +63,335 @@ impl<T> List<T> {
   pub fn head(&self) -> Option<&T> {
     self.head.as_ref().map(|node| &node.elem)
   }
+
+  pub fn len(&self) -> usize {
+    self.head.as_ref().map_or(0, |node| node.len)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.head.is_none()
+  }
+
+  // 是否指向同一个头节点，对 ListFactory 构造出来的链表来说等价于内容相等
+  pub fn ptr_eq(&self, other: &List<T>) -> bool {
+    match (&self.head, &other.head) {
+      (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+      (None, None) => true,
+      _ => false,
+    }
+  }
+
+  // 物理上共享的公共后缀长度（节点是同一个，而不仅仅是值相等）
+  pub fn shares_tail_with(&self, other: &List<T>) -> usize {
+    let (mut a, mut b) = (self.head.as_ref(), other.head.as_ref());
+    let (len_a, len_b) = (self.len(), other.len());
+    // 先让较长的一方走到和另一方一样长，之后同步前进
+    for _ in len_b..len_a {
+      a = a.and_then(|node| node.next.as_ref());
+    }
+    for _ in len_a..len_b {
+      b = b.and_then(|node| node.next.as_ref());
+    }
+
+    let mut remaining = len_a.min(len_b);
+    while let (Some(x), Some(y)) = (a, b) {
+      if Rc::ptr_eq(x, y) {
+        return remaining;
+      }
+      a = x.next.as_ref();
+      b = y.next.as_ref();
+      remaining -= 1;
+    }
+    0
+  }
+
+  // 第 n 个节点，超出长度时返回 None
+  fn node_at(&self, n: usize) -> Option<&Rc<Node<T>>> {
+    let mut link = self.head.as_ref();
+    for _ in 0..n {
+      link = link?.next.as_ref();
+    }
+    link
+  }
+
+  // 第 n 个节点的强引用计数，超出长度时返回 None
+  pub fn strong_count_at(&self, n: usize) -> Option<usize> {
+    self.node_at(n).map(Rc::strong_count)
+  }
+
+  // 下标从头节点开始数
+  pub fn get(&self, index: usize) -> Option<&T> {
+    self.node_at(index).map(|node| &node.elem)
+  }
+
+  // 只被当前链表持有的节点所占的字节数（不含 T 自己在堆上的数据）
+  // 一个节点的引用计数为 1 说明只有前一个节点（或表头）指向它，
+  // 遇到第一个被共享的节点后，后面的都算共享
+  pub fn unique_bytes(&self) -> usize {
+    let mut count = 0;
+    let mut link = self.head.as_ref();
+    while let Some(node) = link {
+      if Rc::strong_count(node) > 1 {
+        break;
+      }
+      count += 1;
+      link = node.next.as_ref();
+    }
+    // Rc 的分配里还有强、弱两个计数
+    count * (std::mem::size_of::<Node<T>>() + 2 * std::mem::size_of::<usize>())
+  }
+}
+
+impl<T: Clone> List<T> {
+  // 写时复制：头节点被共享时复制一份再修改，后面的节点继续共享
+  // 只有 Weak 指向它时（比如被 ListFactory 记录）也会复制，不会影响表里的节点
+  pub fn head_mut(&mut self) -> Option<&mut T> {
+    self.head.as_mut().map(|node| &mut Rc::make_mut(node).elem)
+  }
+
+  // 把前 index 个元素复制一份接到 rest 前面，rest 的节点继续共享
+  fn with_prefix(&self, index: usize, rest: List<T>) -> List<T> {
+    let prefix: Vec<&T> = self.iter().take(index).collect();
+    prefix.into_iter().rev().fold(rest, |list, elem| list.prepend(elem.clone()))
+  }
+
+  // 返回在第 index 个位置（可以等于 len）插入之后的新版本。
+  // 只复制前 index 个节点，原来从 index 开始的后缀被两个版本共享
+  pub fn insert(&self, index: usize, elem: T) -> List<T> {
+    assert!(index <= self.len(), "insertion index (is {index}) should be <= len (is {})", self.len());
+    let rest = List { head: self.node_at(index).cloned() };
+    self.with_prefix(index, rest.prepend(elem))
+  }
+
+  // 和 pop 一样返回被删掉的元素和新版本，index 之后的后缀被共享
+  pub fn remove(&self, index: usize) -> Option<(&T, List<T>)> {
+    let node = self.node_at(index)?;
+    let rest = List { head: node.next.clone() };
+    Some((&node.elem, self.with_prefix(index, rest)))
+  }
+
+  // 和 get_mut 一样写时复制：复制到较大的下标为止，后面的节点继续共享
+  pub fn swap(&mut self, i: usize, j: usize) {
+    let len = self.len();
+    assert!(i < len && j < len, "swap index out of bounds: the len is {len}");
+    let (lo, hi) = (i.min(j), i.max(j));
+    if lo == hi {
+      return;
+    }
+    let mut link = &mut self.head;
+    for _ in 0..lo {
+      link = &mut Rc::make_mut(link.as_mut().unwrap()).next;
+    }
+    let first = Rc::make_mut(link.as_mut().unwrap());
+    let mut link = &mut first.next;
+    for _ in lo + 1..hi {
+      link = &mut Rc::make_mut(link.as_mut().unwrap()).next;
+    }
+    let second = Rc::make_mut(link.as_mut().unwrap());
+    std::mem::swap(&mut first.elem, &mut second.elem);
+  }
+}
+
+// hash-consing：相同的 (elem, next) 只会创建一个节点
+// 由于 next 本身也是被 intern 过的，内容相同的后缀一定是同一串节点，
+// 于是两个链表相等只需要比较头指针
+//
+// 表里存的是 Weak，不会延长节点的生命周期；链表被 drop 后留下的失效项
+// 在插入时按摊还的方式清理，也可以用 purge 立即清理
+pub struct ListFactory<T> {
+  table: HashMap<u64, Vec<Weak<Node<T>>>>,
+  entries: usize,
+  live_at_sweep: usize,
+  inserts_since_sweep: usize,
+}
+
+impl<T: Hash + Eq> ListFactory<T> {
+  pub fn new() -> Self {
+    ListFactory { table: HashMap::new(), entries: 0, live_at_sweep: 0, inserts_since_sweep: 0 }
+  }
+
+  // list 必须是由同一个 factory 构造的，否则它的节点不会被共享
+  pub fn prepend(&mut self, list: &List<T>, elem: T) -> List<T> {
+    let hash = Self::hash(&elem, &list.head);
+    if let Some(node) = self.lookup(hash, &elem, &list.head) {
+      return List { head: Some(node) };
+    }
+
+    // 上次清理之后新建的节点数超过当时的存活数时整体清理一次，
+    // 表项最多是存活节点的两倍左右，清理的开销摊还到每次插入上是 O(1)
+    self.inserts_since_sweep += 1;
+    if self.inserts_since_sweep > self.live_at_sweep {
+      self.purge();
+    }
+
+    let node = Rc::new(Node { elem, len: list.len() + 1, next: list.head.clone() });
+    self.table.entry(hash).or_default().push(Rc::downgrade(&node));
+    self.entries += 1;
+    List { head: Some(node) }
+  }
+
+  // 按迭代顺序构造链表，第一个元素在表头
+  pub fn list<I>(&mut self, iter: I) -> List<T>
+  where
+    I: IntoIterator<Item = T>,
+    I::IntoIter: DoubleEndedIterator,
+  {
+    iter.into_iter().rev().fold(List::new(), |list, elem| self.prepend(&list, elem))
+  }
+
+  // 把任意来源的链表转成共享节点的版本
+  pub fn intern(&mut self, list: &List<T>) -> List<T>
+  where
+    T: Clone,
+  {
+    let elems: Vec<_> = list.iter().cloned().collect();
+    self.list(elems)
+  }
+
+  // 清理所有已经失效的表项
+  pub fn purge(&mut self) {
+    self.table.retain(|_, bucket| {
+      bucket.retain(|weak| weak.strong_count() > 0);
+      !bucket.is_empty()
+    });
+    self.entries = self.table.values().map(Vec::len).sum();
+    self.live_at_sweep = self.entries;
+    self.inserts_since_sweep = 0;
+  }
+
+  // 仍然存活的节点数
+  pub fn live_nodes(&self) -> usize {
+    self.table.values().flatten().filter(|weak| weak.strong_count() > 0).count()
+  }
+
+  fn hash(elem: &T, next: &Link<T>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    elem.hash(&mut hasher);
+    next.as_ref().map(Rc::as_ptr).hash(&mut hasher);
+    hasher.finish()
+  }
+
+  fn lookup(&mut self, hash: u64, elem: &T, next: &Link<T>) -> Option<Rc<Node<T>>> {
+    let bucket = self.table.get_mut(&hash)?;
+    let before = bucket.len();
+    bucket.retain(|weak| weak.strong_count() > 0);
+    self.entries -= before - bucket.len();
+
+    bucket.iter().filter_map(Weak::upgrade).find(|node| {
+      let same_next = match (&node.next, next) {
+        (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+      };
+      same_next && node.elem == *elem
+    })
+  }
+}
+
+impl<T: Hash + Eq> Default for ListFactory<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T> PersistentStack<T> for List<T> {
+  fn push(&self, elem: T) -> Self {
+    self.prepend(elem)
+  }
+
+  fn pop(&self) -> Option<(&T, Self)> {
+    self.head().map(|head| (head, self.tail()))
+  }
+
+  fn peek(&self) -> Option<&T> {
+    self.head()
+  }
+
+  fn len(&self) -> usize {
+    List::len(self)
+  }
+}
+
+impl<T> Index<usize> for List<T> {
+  type Output = T;
+
+  fn index(&self, index: usize) -> &T {
+    let len = self.len();
+    self.get(index).unwrap_or_else(|| panic!("index out of bounds: the len is {len} but the index is {index}"))
+  }
+}
+
+// 写时复制，见 get_mut
+impl<T: Clone> IndexMut<usize> for List<T> {
+  fn index_mut(&mut self, index: usize) -> &mut T {
+    let len = self.len();
+    self.get_mut(index).unwrap_or_else(|| panic!("index out of bounds: the len is {len} but the index is {index}"))
+  }
+}
+
+// 同 stack_ok，这些 trait 都用循环实现，避免长链表爆栈
+impl<T: Debug> Debug for List<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_list().entries(self.iter()).finish()
+  }
+}
+
+// 走到共享的节点时后面一定相同，可以提前结束
+impl<T: PartialEq> PartialEq for List<T> {
+  fn eq(&self, other: &Self) -> bool {
+    if self.len() != other.len() {
+      return false;
+    }
+    let (mut a, mut b) = (self.head.as_ref(), other.head.as_ref());
+    loop {
+      match (a, b) {
+        (None, None) => return true,
+        (Some(x), Some(y)) if Rc::ptr_eq(x, y) => return true,
+        (Some(x), Some(y)) if x.elem == y.elem => {
+          a = x.next.as_ref();
+          b = y.next.as_ref();
+        },
+        _ => return false,
+      }
+    }
+  }
 }
 
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: Hash> Hash for List<T> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    state.write_usize(self.len());
+    self.iter().for_each(|elem| elem.hash(state));
+  }
+}
+
+// 只复制头指针，节点是共享的
+impl<T> Clone for List<T> {
+  fn clone(&self) -> Self {
+    List { head: self.head.clone() }
+  }
+}
+
+impl<T> Default for List<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// 只释放不再被其它版本共享的前缀，逐个取出而不是递归地 drop 节点，
+// 元素的 drop panic 时也会接着释放剩下的节点
 impl<T> Drop for List<T> {
   fn drop(&mut self) {
-    let mut head = self.head.take();
-    while let Some(node) = head {
-      if let Ok(mut inner) = Rc::try_unwrap(node) {
-        head = inner.next.take();
-      } else {
-        break
-      }
+    // 节点还被别的版本持有时只减少引用计数，然后停下
+    fn pop_unique<T>(head: &mut Link<T>) -> Option<T> {
+      let mut inner = Rc::try_unwrap(head.take()?).ok()?;
+      *head = inner.next.take();
+      Some(inner.elem)
     }
+
+    let mut head = self.head.take();
+    util::pop_all(&mut head, pop_unique);
   }
 }
 
@@ -88,8 +419,15 @@ impl<'a, T> Iterator for Iter<'a, T> {
       &node.elem
     })
   }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let len = self.next.map_or(0, |node| node.len);
+    (len, Some(len))
+  }
 }
 
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
 impl<T> List<T> {
   pub fn iter(&self) -> Iter<'_, T> {
     Iter {
@@ -98,18 +436,64 @@ impl<T> List<T> {
   }
 }
 
-// Rc 无内部可变性
-// pub struct IterMut<'a, T> {
-//   next: Option<&'a mut Node<T>>
-// }
+// Rc 无内部可变性，只能在 T: Clone 时写时复制：
+// 经过的节点如果被共享就复制一份，没被共享的原地修改
+pub struct IterMut<'a, T> {
+  next: Option<&'a mut Rc<Node<T>>>
+}
 
-// impl<'a, T> Iterator for IterMut<'a, T> {
-//   type Item = &'a mut T;
+impl<'a, T: Clone> Iterator for IterMut<'a, T> {
+  type Item = &'a mut T;
 
-//   fn next(&mut self) -> Option<Self::Item> {
+  fn next(&mut self) -> Option<Self::Item> {
+    self.next.take().map(|node| {
+      let node = Rc::make_mut(node);
+      self.next = node.next.as_mut();
+      &mut node.elem
+    })
+  }
 
-//   }
-// }
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let len = self.next.as_ref().map_or(0, |node| node.len);
+    (len, Some(len))
+  }
+}
+
+impl<T: Clone> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T: Clone> List<T> {
+  // 惰性的：只有真正走到的节点才会被复制
+  pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+    IterMut { next: self.head.as_mut() }
+  }
+
+  // 复制第 n 个节点之前（含）所有被共享的节点，后面的继续共享
+  pub fn get_mut(&mut self, n: usize) -> Option<&mut T> {
+    let mut link = &mut self.head;
+    for _ in 0..n {
+      link = &mut Rc::make_mut(link.as_mut()?).next;
+    }
+    link.as_mut().map(|node| &mut Rc::make_mut(node).elem)
+  }
+}
+
+// 遇到图里已有的节点就停下，多个版本共享的后缀只画一次
+impl<T: Debug> Visualize for List<T> {
+  fn visualize(&self, name: &str, graph: &mut Graph) {
+    graph.add_root(name, self.head.as_ref().map(|node| Rc::as_ptr(node) as usize), None);
+    let mut link = self.head.as_ref();
+    while let Some(node) = link {
+      let address = Rc::as_ptr(node) as usize;
+      if !graph.add_node(address, format!("{:?}", node.elem), Some(Rc::strong_count(node))) {
+        break;
+      }
+      link = node.next.as_ref();
+      if let Some(next) = link {
+        graph.add_edge(address, Rc::as_ptr(next) as usize, EdgeKind::Next);
+      }
+    }
+  }
+}
 
 #[cfg(test)]
 mod tests {
@@ -132,6 +516,36 @@ mod tests {
     assert_eq!(list.head(), None);
   }
 
+  #[test]
+  fn index() {
+    let list: List<i32> = List::new().prepend(5).prepend(4).prepend(3).prepend(1).prepend(0);
+    assert_eq!((list.get(0), list.get(4), list.get(5)), (Some(&0), Some(&5), None));
+    assert_eq!(list[3], 4);
+
+    // 新版本只复制插入点之前的节点，后缀 [3, 4, 5] 是同一串
+    let inserted = list.insert(2, 2);
+    assert_eq!(format!("{:?}", inserted), "[0, 1, 2, 3, 4, 5]");
+    assert_eq!(inserted.shares_tail_with(&list), 3);
+    assert_eq!(list.insert(5, 6).shares_tail_with(&list), 0);
+    assert!(list.insert(0, -1).tail().ptr_eq(&list));
+
+    let (removed, shorter) = inserted.remove(4).unwrap();
+    assert_eq!(*removed, 4);
+    assert_eq!(format!("{:?}", shorter), "[0, 1, 2, 3, 5]");
+    assert_eq!(shorter.shares_tail_with(&inserted), 1);
+    assert!(list.remove(0).unwrap().1.ptr_eq(&list.tail()));
+    assert!(list.remove(5).is_none());
+    assert_eq!(format!("{:?}", list), "[0, 1, 3, 4, 5]");
+
+    // swap 和 IndexMut 写时复制，不影响其它版本
+    let mut swapped = list.clone();
+    swapped.swap(3, 1);
+    swapped[0] = 10;
+    assert_eq!(format!("{:?}", swapped), "[10, 4, 3, 1, 5]");
+    assert_eq!(format!("{:?}", list), "[0, 1, 3, 4, 5]");
+    assert_eq!(swapped.shares_tail_with(&list), 1);
+  }
+
   #[test]
   fn iter_fn() {
     let list = List::new();
@@ -142,4 +556,175 @@ mod tests {
     assert_eq!(iter.next(), Some(&1));
     assert_eq!(iter.next(), None);
   }
+
+  #[test]
+  fn len() {
+    let list = List::new();
+    assert!(list.is_empty());
+    let list = list.prepend(1).prepend(2).prepend(3);
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.tail().len(), 2);
+    assert_eq!(list.tail().tail().tail().len(), 0);
+
+    let mut iter = list.iter();
+    assert_eq!(iter.len(), 3);
+    iter.next();
+    assert_eq!(iter.size_hint(), (2, Some(2)));
+
+    let mut copy = list.clone();
+    let mut iter_mut = copy.iter_mut();
+    iter_mut.next();
+    assert_eq!(iter_mut.len(), 2);
+
+    let mut factory = ListFactory::new();
+    assert_eq!(factory.list(vec![1, 2, 3, 4]).len(), 4);
+  }
+
+  #[test]
+  fn sharing_introspection() {
+    let base = List::new().prepend(1).prepend(2).prepend(3);
+    let a = base.prepend(4);
+    let b = base.tail().prepend(5).prepend(6);
+    let copy = List::new().prepend(1).prepend(2).prepend(3);
+
+    assert!(base.ptr_eq(&base.clone()));
+    assert!(!base.ptr_eq(&copy));
+    assert_eq!(a.shares_tail_with(&base), 3);
+    assert_eq!(a.shares_tail_with(&b), 2);
+    assert_eq!(b.shares_tail_with(&a), 2);
+    // 值相等但节点不同
+    assert_eq!(copy.shares_tail_with(&base), 0);
+    assert_eq!(a.shares_tail_with(&List::new()), 0);
+
+    // base 的头节点被 base 和 a 的节点引用，1 被 2 引用
+    assert_eq!(a.strong_count_at(0), Some(1));
+    assert_eq!(a.strong_count_at(1), Some(2));
+    assert_eq!(a.strong_count_at(2), Some(2));
+    assert_eq!(a.strong_count_at(3), Some(1));
+    assert_eq!(a.strong_count_at(4), None);
+
+    let node_bytes = std::mem::size_of::<Node<i32>>() + 2 * std::mem::size_of::<usize>();
+    assert_eq!(a.unique_bytes(), node_bytes);
+    assert_eq!(b.unique_bytes(), 2 * node_bytes);
+    assert_eq!(copy.unique_bytes(), 3 * node_bytes);
+    assert_eq!(base.unique_bytes(), 0);
+  }
+
+  #[test]
+  fn head_mut_copy_on_write() {
+    let base = List::new().prepend(1).prepend(2);
+    let mut shared = base.clone();
+    *shared.head_mut().unwrap() = 20;
+    assert_eq!(shared.head(), Some(&20));
+    assert_eq!(base.head(), Some(&2));
+    // 只复制了头节点
+    assert_eq!(shared.shares_tail_with(&base), 1);
+
+    // 没有共享时原地修改
+    let mut owned = List::new().prepend(1);
+    let before = owned.head().unwrap() as *const i32;
+    *owned.head_mut().unwrap() = 10;
+    assert_eq!(owned.head().unwrap() as *const i32, before);
+    assert_eq!(List::<i32>::new().head_mut(), None);
+
+    // 被 intern 的节点不会被原地修改
+    let mut factory = ListFactory::new();
+    let mut interned = factory.list(vec![1, 2]);
+    *interned.head_mut().unwrap() = 7;
+    assert_eq!(factory.list(vec![1, 2]).head(), Some(&1));
+  }
+
+  #[test]
+  fn iter_mut_copy_on_write() {
+    let base = List::new().prepend(1).prepend(2).prepend(3);
+    let mut version = base.clone();
+    for elem in version.iter_mut() {
+      *elem *= 10;
+    }
+    assert_eq!(version.iter().copied().collect::<Vec<_>>(), vec![30, 20, 10]);
+    assert_eq!(base.iter().copied().collect::<Vec<_>>(), vec![3, 2, 1]);
+    assert_eq!(version.shares_tail_with(&base), 0);
+
+    // 没有被共享的节点原地修改
+    let before: Vec<_> = version.iter().map(|elem| elem as *const i32).collect();
+    for elem in version.iter_mut() {
+      *elem += 1;
+    }
+    let after: Vec<_> = version.iter().map(|elem| elem as *const i32).collect();
+    assert_eq!(before, after);
+
+    // 只走前两步时第三个节点保持共享
+    let mut partial = base.clone();
+    partial.iter_mut().take(2).for_each(|elem| *elem = 0);
+    assert_eq!(partial.iter().copied().collect::<Vec<_>>(), vec![0, 0, 1]);
+    assert_eq!(partial.shares_tail_with(&base), 1);
+  }
+
+  #[test]
+  fn get_mut_copy_on_write() {
+    let base = List::new().prepend(1).prepend(2).prepend(3);
+    let mut version = base.prepend(4);
+
+    // 头节点只属于 version，不需要复制任何节点
+    *version.get_mut(0).unwrap() = 40;
+    assert_eq!(version.shares_tail_with(&base), 3);
+
+    // 修改第 2 个节点需要复制它和它前面被共享的节点
+    *version.get_mut(2).unwrap() = 20;
+    assert_eq!(version.iter().copied().collect::<Vec<_>>(), vec![40, 3, 20, 1]);
+    assert_eq!(base.iter().copied().collect::<Vec<_>>(), vec![3, 2, 1]);
+    assert_eq!(version.shares_tail_with(&base), 1);
+
+    assert_eq!(version.get_mut(4), None);
+    assert_eq!(version.get_mut(100), None);
+  }
+
+  #[test]
+  fn factory_shares_suffixes() {
+    let mut factory = ListFactory::new();
+    let a = factory.list(vec![1, 2, 3, 4]);
+    let b = factory.list(vec![9, 3, 4]);
+    let c = factory.list(vec![1, 2, 3, 4]);
+
+    assert!(a.ptr_eq(&c));
+    assert!(!a.ptr_eq(&b));
+    assert!(a.tail().tail().ptr_eq(&b.tail()));
+    // 1 2 3 4 和 9 共 5 个节点
+    assert_eq!(factory.live_nodes(), 5);
+
+    // 不同来源的链表 intern 之后也会共享
+    let d = factory.intern(&List::new().prepend(4).prepend(3).prepend(9));
+    assert!(d.ptr_eq(&b));
+    assert!(List::<i32>::new().ptr_eq(&factory.list(vec![])));
+  }
+
+  #[test]
+  fn factory_drops_dead_entries() {
+    let mut factory = ListFactory::new();
+    let keep = factory.list(vec![1, 2]);
+    {
+      let _temp = factory.list(vec![5, 6, 7]);
+      assert_eq!(factory.live_nodes(), 5);
+    }
+    // 表里只有 Weak，链表 drop 后节点就释放了
+    assert_eq!(factory.live_nodes(), 2);
+    factory.purge();
+    assert_eq!(factory.entries, 2);
+
+    // 重新构造时得到新的节点，旧的失效项不会被误用
+    let again = factory.list(vec![5, 6, 7]);
+    assert_eq!(again.iter().copied().collect::<Vec<_>>(), vec![5, 6, 7]);
+    assert!(keep.ptr_eq(&factory.list(vec![1, 2])));
+  }
+
+  #[test]
+  fn factory_sweeps_automatically() {
+    let mut factory = ListFactory::new();
+    for i in 0..1000 {
+      let _list = factory.list(vec![i, i + 1]);
+    }
+    // 摊还清理保证表项数量和存活节点数同阶
+    assert!(factory.entries <= 4);
+    assert_eq!(factory.live_nodes(), 0);
+  }
 }
\ No newline at end of file
@@ -0,0 +1,2 @@
+pub mod sorted_list;
+pub mod pairing_heap;
@@ -0,0 +1,382 @@
+// 配对堆（最小堆），节点用 child/sibling 两个裸指针串成一棵多叉树
+// push、meld 都只是一次 link，O(1)；pop 做两趟配对合并，均摊 O(log n)
+// decrease_key 通过 push 时返回的 Handle 找到节点，把它所在的子树剪下来再和根 link
+
+use std::cell::{Cell, RefCell};
+use std::ptr;
+use std::rc::Rc;
+
+type Link<T> = *mut Node<T>;
+
+struct Node<T> {
+  elem: T,
+  // 最左边的孩子
+  child: Link<T>,
+  // 右边的兄弟
+  sibling: Link<T>,
+  // 如果是最左边的孩子，指向父节点；否则指向左边的兄弟。根节点为 null
+  prev: Link<T>,
+  slot: Rc<Slot<T>>,
+}
+
+// 节点和 Handle 共享的一小块状态，节点被弹出或者堆被释放时 node 会被置为 null
+struct Slot<T> {
+  node: Cell<Link<T>>,
+  owner: Rc<Owner>,
+}
+
+// 标识节点属于哪个堆。meld 之后被合并的堆的 Owner 指向新的堆，就像并查集一样
+struct Owner {
+  parent: RefCell<Option<Rc<Owner>>>,
+}
+
+impl Owner {
+  fn new() -> Rc<Owner> {
+    Rc::new(Owner { parent: RefCell::new(None) })
+  }
+
+  fn find(owner: &Rc<Owner>) -> Rc<Owner> {
+    let mut root = owner.clone();
+    loop {
+      let parent = root.parent.borrow().clone();
+      match parent {
+        Some(parent) => root = parent,
+        None => break,
+      }
+    }
+    // 路径压缩
+    let mut cur = owner.clone();
+    while !Rc::ptr_eq(&cur, &root) {
+      let next = cur.parent.replace(Some(root.clone())).unwrap();
+      cur = next;
+    }
+    root
+  }
+}
+
+pub struct Handle<T>(Rc<Slot<T>>);
+
+impl<T> Clone for Handle<T> {
+  fn clone(&self) -> Self {
+    Handle(self.0.clone())
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandleError {
+  // 节点已经被弹出，或者所在的堆已经被释放
+  Removed,
+  // Handle 属于另一个堆
+  ForeignHeap,
+  // decrease_key 只能把元素变小
+  KeyIncreased,
+}
+
+pub struct PairingHeap<T> {
+  root: Link<T>,
+  len: usize,
+  owner: Rc<Owner>,
+}
+
+impl<T: Ord> PairingHeap<T> {
+  pub fn new() -> Self {
+    PairingHeap { root: ptr::null_mut(), len: 0, owner: Owner::new() }
+  }
+
+  pub fn push(&mut self, elem: T) -> Handle<T> {
+    let slot = Rc::new(Slot { node: Cell::new(ptr::null_mut()), owner: self.owner.clone() });
+    let node = Box::into_raw(Box::new(Node {
+      elem,
+      child: ptr::null_mut(),
+      sibling: ptr::null_mut(),
+      prev: ptr::null_mut(),
+      slot: slot.clone(),
+    }));
+    slot.node.set(node);
+    self.root = unsafe { Self::link(self.root, node) };
+    self.len += 1;
+    Handle(slot)
+  }
+
+  pub fn pop(&mut self) -> Option<T> {
+    if self.root.is_null() {
+      return None;
+    }
+    unsafe {
+      let old_root = Box::from_raw(self.root);
+      old_root.slot.node.set(ptr::null_mut());
+      self.root = Self::merge_pairs(old_root.child);
+      self.len -= 1;
+      Some(old_root.elem)
+    }
+  }
+
+  // 把 other 的所有节点并入 self，other 里的 Handle 之后对 self 有效
+  pub fn meld(&mut self, mut other: PairingHeap<T>) {
+    if other.root.is_null() {
+      return;
+    }
+    *other.owner.parent.borrow_mut() = Some(self.owner.clone());
+    self.root = unsafe { Self::link(self.root, other.root) };
+    self.len += other.len;
+    other.root = ptr::null_mut();
+    other.len = 0;
+  }
+
+  pub fn decrease_key(&mut self, handle: &Handle<T>, elem: T) -> Result<(), HandleError> {
+    let node = self.resolve(handle)?;
+    unsafe {
+      if elem > (*node).elem {
+        return Err(HandleError::KeyIncreased);
+      }
+      (*node).elem = elem;
+      if node == self.root {
+        return Ok(());
+      }
+
+      // 从父节点（或左兄弟）那里剪下以 node 为根的子树
+      let prev = (*node).prev;
+      if (*prev).child == node {
+        (*prev).child = (*node).sibling;
+      } else {
+        (*prev).sibling = (*node).sibling;
+      }
+      if !(*node).sibling.is_null() {
+        (*(*node).sibling).prev = prev;
+      }
+      (*node).sibling = ptr::null_mut();
+      (*node).prev = ptr::null_mut();
+
+      self.root = Self::link(self.root, node);
+    }
+    Ok(())
+  }
+
+  // a 和 b 都必须是独立的根（没有 prev 和 sibling），较小的一个成为新根
+  unsafe fn link(a: Link<T>, b: Link<T>) -> Link<T> {
+    if a.is_null() {
+      return b;
+    }
+    if b.is_null() {
+      return a;
+    }
+    let (parent, child) = if (*b).elem < (*a).elem { (b, a) } else { (a, b) };
+    (*child).sibling = (*parent).child;
+    if !(*parent).child.is_null() {
+      (*(*parent).child).prev = child;
+    }
+    (*child).prev = parent;
+    (*parent).child = child;
+    parent
+  }
+
+  // 两趟合并：从左到右两两 link，再从右到左依次合并
+  unsafe fn merge_pairs(first: Link<T>) -> Link<T> {
+    // 第一趟的结果借用 sibling 串成一个栈，栈顶是最右边的一对
+    let mut stack: Link<T> = ptr::null_mut();
+    let mut cur = first;
+    while !cur.is_null() {
+      let a = cur;
+      let b = (*a).sibling;
+      cur = if b.is_null() { ptr::null_mut() } else { (*b).sibling };
+      Self::detach(a);
+      let merged = if b.is_null() {
+        a
+      } else {
+        Self::detach(b);
+        Self::link(a, b)
+      };
+      (*merged).sibling = stack;
+      stack = merged;
+    }
+
+    let mut root: Link<T> = ptr::null_mut();
+    while !stack.is_null() {
+      let merged = stack;
+      stack = (*merged).sibling;
+      (*merged).sibling = ptr::null_mut();
+      root = Self::link(merged, root);
+    }
+    root
+  }
+}
+
+impl<T> PairingHeap<T> {
+  pub fn peek(&self) -> Option<&T> {
+    unsafe { self.root.as_ref().map(|node| &node.elem) }
+  }
+
+  pub fn get(&self, handle: &Handle<T>) -> Result<&T, HandleError> {
+    let node = self.resolve(handle)?;
+    Ok(unsafe { &(*node).elem })
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  fn resolve(&self, handle: &Handle<T>) -> Result<Link<T>, HandleError> {
+    let node = handle.0.node.get();
+    if node.is_null() {
+      return Err(HandleError::Removed);
+    }
+    if !Rc::ptr_eq(&Owner::find(&handle.0.owner), &self.owner) {
+      return Err(HandleError::ForeignHeap);
+    }
+    Ok(node)
+  }
+
+  unsafe fn detach(node: Link<T>) {
+    (*node).sibling = ptr::null_mut();
+    (*node).prev = ptr::null_mut();
+  }
+}
+
+impl<T: Ord> Default for PairingHeap<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T> Drop for PairingHeap<T> {
+  fn drop(&mut self) {
+    // 树可能很深，用显式的栈代替递归
+    let mut stack = vec![self.root];
+    self.root = ptr::null_mut();
+    while let Some(node) = stack.pop() {
+      if node.is_null() {
+        continue;
+      }
+      let node = unsafe { Box::from_raw(node) };
+      node.slot.node.set(ptr::null_mut());
+      stack.push(node.child);
+      stack.push(node.sibling);
+    }
+  }
+}
+
+pub struct IntoIter<T: Ord>(PairingHeap<T>);
+
+impl<T: Ord> IntoIterator for PairingHeap<T> {
+  type Item = T;
+  type IntoIter = IntoIter<T>;
+
+  fn into_iter(self) -> IntoIter<T> {
+    IntoIter(self)
+  }
+}
+
+// 按从小到大的顺序弹出
+impl<T: Ord> Iterator for IntoIter<T> {
+  type Item = T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.0.pop()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn basic() {
+    let mut heap = PairingHeap::new();
+    assert_eq!(heap.pop(), None);
+
+    for elem in [5, 3, 8, 1, 9, 2, 7] {
+      heap.push(elem);
+    }
+    assert_eq!(heap.len(), 7);
+    assert_eq!(heap.peek(), Some(&1));
+    assert_eq!(heap.into_iter().collect::<Vec<_>>(), vec![1, 2, 3, 5, 7, 8, 9]);
+  }
+
+  #[test]
+  fn meld() {
+    let mut a = PairingHeap::new();
+    let mut b = PairingHeap::new();
+    for i in (0..10).step_by(2) {
+      a.push(i);
+    }
+    let handle = b.push(7);
+    for i in (1..10).step_by(2) {
+      b.push(i);
+    }
+    assert_eq!(a.decrease_key(&handle, 0), Err(HandleError::ForeignHeap));
+
+    a.meld(b);
+    assert_eq!(a.len(), 11);
+    // meld 之后 b 的 Handle 对 a 生效
+    assert_eq!(a.get(&handle), Ok(&7));
+    a.decrease_key(&handle, -1).unwrap();
+    assert_eq!(a.into_iter().collect::<Vec<_>>(), vec![-1, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+  }
+
+  #[test]
+  fn decrease_key() {
+    let mut heap = PairingHeap::new();
+    let handles: Vec<_> = (0..20).map(|i| heap.push(i * 10)).collect();
+    assert_eq!(heap.pop(), Some(0));
+    assert_eq!(heap.decrease_key(&handles[0], -5), Err(HandleError::Removed));
+    assert_eq!(heap.decrease_key(&handles[5], 51), Err(HandleError::KeyIncreased));
+
+    heap.decrease_key(&handles[15], 5).unwrap();
+    heap.decrease_key(&handles[7], 15).unwrap();
+    heap.decrease_key(&handles[19], -1).unwrap();
+    assert_eq!(heap.get(&handles[19]), Ok(&-1));
+    assert_eq!(heap.peek(), Some(&-1));
+
+    let mut expected: Vec<_> = (1..20).map(|i| i * 10).collect();
+    expected[14] = 5;
+    expected[6] = 15;
+    expected[18] = -1;
+    expected.sort();
+    assert_eq!(heap.into_iter().collect::<Vec<_>>(), expected);
+    // 堆被释放以后 Handle 失效
+    assert_eq!(handles[3].0.node.get(), ptr::null_mut());
+  }
+
+  #[test]
+  fn decrease_key_after_pops() {
+    // pop 会重排整棵树，之后 Handle 依旧指向正确的节点
+    let mut heap = PairingHeap::new();
+    let mut handles = Vec::new();
+    let mut x = 12345u32;
+    for _ in 0..200 {
+      x = x.wrapping_mul(1103515245).wrapping_add(12345);
+      let key = (x >> 8) as i64 % 1000;
+      handles.push((heap.push(key), key));
+    }
+    let mut model: Vec<_> = handles.iter().map(|&(_, key)| key).collect();
+    for round in 0..100 {
+      let min = heap.pop().unwrap();
+      let pos = model.iter().position(|&key| key == min).unwrap();
+      model.remove(pos);
+
+      let (handle, _) = &handles[(round * 7) % handles.len()];
+      if let Ok(&cur) = heap.get(handle) {
+        heap.decrease_key(handle, cur - 500).unwrap();
+        let pos = model.iter().position(|&key| key == cur).unwrap();
+        model[pos] = cur - 500;
+      }
+    }
+    model.sort();
+    assert_eq!(heap.into_iter().collect::<Vec<_>>(), model);
+  }
+
+  #[test]
+  #[cfg_attr(miri, ignore)]
+  fn long_heap() {
+    // 顺序插入会产生一条很深的链，Drop 不能递归
+    let mut heap = PairingHeap::new();
+    for i in (0..100000).rev() {
+      heap.push(i);
+    }
+    drop(heap);
+  }
+}
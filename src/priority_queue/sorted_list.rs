@@ -0,0 +1,182 @@
+// 用有序单链表实现的优先队列，最小的元素永远在表头
+// insert 需要找到插入位置，O(n)；pop_min 只需要摘掉表头，O(1)
+
+type Link<T> = Option<Box<Node<T>>>;
+
+struct Node<T> {
+  elem: T,
+  next: Link<T>,
+}
+
+pub struct SortedList<T> {
+  head: Link<T>,
+  len: usize,
+}
+
+impl<T: Ord> SortedList<T> {
+  pub fn new() -> Self {
+    SortedList { head: None, len: 0 }
+  }
+
+  // 相等的元素插在已有元素之后，保证相等元素先进先出
+  pub fn insert(&mut self, elem: T) {
+    let mut cur = &mut self.head;
+    // while let Some(node) = cur { if node.elem > elem { break } cur = &mut node.next; }
+    // 上面这种写法会被 NLL 拒绝（break 之后 cur 仍被视为处于借用中），所以先判断再重新借用
+    while cur.as_ref().is_some_and(|node| node.elem <= elem) {
+      cur = &mut cur.as_mut().unwrap().next;
+    }
+    let next = cur.take();
+    *cur = Some(Box::new(Node { elem, next }));
+    self.len += 1;
+  }
+}
+
+impl<T> SortedList<T> {
+  pub fn pop_min(&mut self) -> Option<T> {
+    self.head.take().map(|node| {
+      self.head = node.next;
+      self.len -= 1;
+      node.elem
+    })
+  }
+
+  pub fn peek_min(&self) -> Option<&T> {
+    self.head.as_ref().map(|node| &node.elem)
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.head.is_none()
+  }
+
+  pub fn iter(&self) -> Iter<'_, T> {
+    Iter { next: self.head.as_deref() }
+  }
+}
+
+impl<T: Ord> Default for SortedList<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: Ord> FromIterator<T> for SortedList<T> {
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    let mut list = SortedList::new();
+    for elem in iter {
+      list.insert(elem);
+    }
+    list
+  }
+}
+
+impl<T> Drop for SortedList<T> {
+  fn drop(&mut self) {
+    let mut cur_link = self.head.take();
+    while let Some(mut node) = cur_link {
+      cur_link = node.next.take();
+    }
+  }
+}
+
+pub struct IntoIter<T>(SortedList<T>);
+
+impl<T> IntoIterator for SortedList<T> {
+  type Item = T;
+  type IntoIter = IntoIter<T>;
+
+  fn into_iter(self) -> IntoIter<T> {
+    IntoIter(self)
+  }
+}
+
+impl<T> Iterator for IntoIter<T> {
+  type Item = T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.0.pop_min()
+  }
+}
+
+pub struct Iter<'a, T> {
+  next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+  type Item = &'a T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.next.map(|node| {
+      self.next = node.next.as_deref();
+      &node.elem
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn basic() {
+    let mut list = SortedList::new();
+    assert_eq!(list.pop_min(), None);
+
+    list.insert(3);
+    list.insert(1);
+    list.insert(2);
+    list.insert(1);
+    assert_eq!(list.len(), 4);
+    assert_eq!(list.peek_min(), Some(&1));
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec![&1, &1, &2, &3]);
+
+    assert_eq!(list.pop_min(), Some(1));
+    assert_eq!(list.pop_min(), Some(1));
+    assert_eq!(list.pop_min(), Some(2));
+    assert_eq!(list.pop_min(), Some(3));
+    assert_eq!(list.pop_min(), None);
+    assert!(list.is_empty());
+  }
+
+  #[test]
+  fn stable_for_equal_keys() {
+    #[derive(Debug)]
+    struct Job(u32, &'static str);
+    impl PartialEq for Job {
+      fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+      }
+    }
+    impl Eq for Job {}
+    impl PartialOrd for Job {
+      fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+      }
+    }
+    impl Ord for Job {
+      fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+      }
+    }
+
+    let list: SortedList<_> = vec![Job(2, "a"), Job(1, "b"), Job(2, "c"), Job(1, "d")].into_iter().collect();
+    let names: Vec<_> = list.into_iter().map(|job| job.1).collect();
+    assert_eq!(names, vec!["b", "d", "a", "c"]);
+  }
+
+  #[test]
+  #[cfg_attr(miri, ignore)]
+  fn long_list() {
+    // 插入是 O(n)，所以倒序插入让每次都落在表头，只测试 Drop 不会爆栈
+    let mut list = SortedList::new();
+    for i in (0..100000).rev() {
+      list.insert(i);
+    }
+    assert_eq!(list.len(), 100000);
+    drop(list);
+  }
+}
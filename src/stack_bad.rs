@@ -4,20 +4,18 @@
 //   Cons(i32, Box<List>),
 //   Nil,
 // }
-#[allow(dead_code)]
-#[derive(Debug, Clone, PartialEq, Eq)]
+// derive 出来的 Debug、Clone、PartialEq 会沿着 next 递归，长链表会爆栈，
+// 所以这些 trait 都在 List 上用循环手写
 pub enum Link {
   Empty,
   More(Box<Node>),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Node {
   elem: i32,
   next: Link
 }
 
-#[derive(Debug)]
 pub struct List {
   head: Link
 }
@@ -39,7 +37,7 @@ impl List {
 
   pub fn pop(&mut self) -> Option<i32> {
     match std::mem::replace(&mut self.head, Link::Empty) {
-      Link::Empty => return None,
+      Link::Empty => None,
       Link::More(node) => {
         self.head = node.next;
         Some(node.elem)
@@ -48,6 +46,145 @@ impl List {
   }
 }
 
+impl List {
+  fn iter(&self) -> impl Iterator<Item = &i32> {
+    let mut link = &self.head;
+    std::iter::from_fn(move || match link {
+      Link::Empty => None,
+      Link::More(node) => {
+        link = &node.next;
+        Some(&node.elem)
+      }
+    })
+  }
+
+  // 第 index 个 Link（index == 长度时是末尾的 Empty），超出长度返回 None
+  fn link_mut(&mut self, index: usize) -> Option<&mut Link> {
+    let mut link = &mut self.head;
+    for _ in 0..index {
+      match link {
+        Link::More(node) => link = &mut node.next,
+        Link::Empty => return None,
+      }
+    }
+    Some(link)
+  }
+
+  // 下标从栈顶开始数
+  pub fn get(&self, index: usize) -> Option<&i32> {
+    self.iter().nth(index)
+  }
+
+  pub fn get_mut(&mut self, index: usize) -> Option<&mut i32> {
+    match self.link_mut(index)? {
+      Link::More(node) => Some(&mut node.elem),
+      Link::Empty => None,
+    }
+  }
+
+  // index 可以等于长度，相当于插到栈底
+  pub fn insert(&mut self, index: usize, elem: i32) {
+    let link = self.link_mut(index).expect("insertion index out of bounds");
+    let next = std::mem::replace(link, Link::Empty);
+    *link = Link::More(Box::new(Node { elem, next }));
+  }
+
+  pub fn remove(&mut self, index: usize) -> Option<i32> {
+    let link = self.link_mut(index)?;
+    match std::mem::replace(link, Link::Empty) {
+      Link::Empty => None,
+      Link::More(node) => {
+        *link = node.next;
+        Some(node.elem)
+      }
+    }
+  }
+
+  // i32 是 Copy，直接读出来再写回去
+  pub fn swap(&mut self, i: usize, j: usize) {
+    let (a, b) = (self[i], self[j]);
+    self[i] = b;
+    self[j] = a;
+  }
+}
+
+impl std::ops::Index<usize> for List {
+  type Output = i32;
+
+  fn index(&self, index: usize) -> &i32 {
+    self.get(index).expect("index out of bounds")
+  }
+}
+
+impl std::ops::IndexMut<usize> for List {
+  fn index_mut(&mut self, index: usize) -> &mut i32 {
+    self.get_mut(index).expect("index out of bounds")
+  }
+}
+
+impl std::fmt::Debug for List {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_list().entries(self.iter()).finish()
+  }
+}
+
+impl PartialEq for List {
+  fn eq(&self, other: &Self) -> bool {
+    self.iter().eq(other.iter())
+  }
+}
+
+impl Eq for List {}
+
+impl std::hash::Hash for List {
+  fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    state.write_usize(self.iter().count());
+    self.iter().for_each(|elem| elem.hash(state));
+  }
+}
+
+// 顺着一个指向链尾的游标往后接，而不是递归地 clone next
+impl Clone for List {
+  fn clone(&self) -> Self {
+    let mut list = List::new();
+    let mut tail = &mut list.head;
+    for elem in self.iter() {
+      *tail = Link::More(Box::new(Node { elem: *elem, next: Link::Empty }));
+      if let Link::More(node) = tail {
+        tail = &mut node.next;
+      }
+    }
+    list
+  }
+}
+
+// 没有维护长度，len 只能数一遍
+impl crate::traits::Stack<i32> for List {
+  type Peek<'a> = &'a i32;
+
+  fn push(&mut self, elem: i32) {
+    List::push(self, elem)
+  }
+
+  fn pop(&mut self) -> Option<i32> {
+    List::pop(self)
+  }
+
+  fn peek(&self) -> Option<&i32> {
+    self.iter().next()
+  }
+
+  fn len(&self) -> usize {
+    self.iter().count()
+  }
+}
+
+impl Default for List {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl Drop for List {
   fn drop(&mut self) {
     let mut cur_link = std::mem::replace(&mut self.head, Link::Empty);
@@ -71,13 +208,36 @@ mod tests {
     assert_eq!(list.pop(), Some(2));
     assert_eq!(list.pop(), Some(1));
   }
-  
+
+  #[test]
+  fn index() {
+    let mut list = List::new();
+    list.push(3);
+    list.push(1);
+    assert_eq!((list.get(0), list.get(1), list.get(2)), (Some(&1), Some(&3), None));
+
+    // [1, 3] -> [0, 1, 2, 3] -> [0, 1, 2, 3, 4]
+    list.insert(1, 2);
+    list.insert(0, 0);
+    list.insert(4, 4);
+    assert_eq!(format!("{:?}", list), "[0, 1, 2, 3, 4]");
+
+    list[2] = 20;
+    *list.get_mut(3).unwrap() += 10;
+    list.swap(0, 4);
+    assert_eq!(format!("{:?}", list), "[4, 1, 20, 13, 0]");
+    assert_eq!(list.remove(2), Some(20));
+    assert_eq!(list.remove(3), Some(0));
+    assert_eq!(list.remove(3), None);
+    assert_eq!((list[0], list[2]), (4, 13));
+    assert_eq!(list.get_mut(3), None);
+  }
+
   #[test]
-  fn long_list() {
-    // let mut list = List::new();
-    // for i in 0..100000000 {
-    //   list.push(i);
-    // }
-    // drop(list);
+  #[should_panic(expected = "insertion index out of bounds")]
+  fn insert_out_of_bounds() {
+    let mut list = List::new();
+    list.push(1);
+    list.insert(2, 2);
   }
 }
\ No newline at end of file
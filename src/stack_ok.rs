@@ -1,4 +1,11 @@
-#[allow(dead_code)]
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::{Index, IndexMut};
+
+use crate::debug_viz::{EdgeKind, Graph, Visualize};
+use crate::traits::Stack;
+use crate::util::{self, is_zst, zst};
 
 // 使用 Option 替代之前的 enum
 // pub enum Link {
@@ -7,114 +14,342 @@
 // }
 type Link<T> = Option<Box<Node<T>>>;
 
-#[derive(Debug, PartialEq, Eq)]
+// 不 derive Debug、PartialEq 这些 trait：derive 出来的实现会沿着 next 递归，
+// 一百万个节点就能爆栈，和 Drop 的问题一样。List 上用循环手写
 pub struct Node<T> {
   elem: T,
   next: Link<T>
 }
 
-#[derive(Debug)]
+// T 是零大小类型（()、标记类型）时不分配节点，head 一直是 None，只靠 len 记数。
+// 零大小的值不占内存，对齐的悬垂指针就是它合法的地址
 pub struct List<T> {
-  head: Link<T>
+  head: Link<T>,
+  len: usize,
 }
 
 impl<T> List<T> {
   pub fn new() -> Self {
     List {
-      head: Link::None
+      head: Link::None,
+      len: 0,
     }
   }
 
   pub fn push(&mut self, elem: T) {
+    if is_zst::<T>() {
+      // 值的所有权交给链表，pop 时再原样读出来，drop 也留到那时候
+      mem::forget(elem);
+      self.len += 1;
+      return;
+    }
     let new_node = Box::new(Node {
       elem,
       next: self.head.take(),
     });
     self.head = Link::Some(new_node);
+    self.len += 1;
   }
 
   pub fn pop(&mut self) -> Option<T> {
+    if is_zst::<T>() {
+      if self.len == 0 {
+        return None;
+      }
+      self.len -= 1;
+      return Some(unsafe { std::ptr::read(zst::<T>()) });
+    }
     self.head.take().map(|node| {
       self.head = node.next;
+      self.len -= 1;
       node.elem
     })
   }
 
   pub fn peek(&self) -> Option<&T> {
+    if is_zst::<T>() {
+      return (self.len > 0).then(|| unsafe { &*zst::<T>() });
+    }
     self.head.as_ref().map(|node| {
       &node.elem
     })
   }
 
   pub fn peek_mut(&mut self) -> Option<&mut T> {
+    if is_zst::<T>() {
+      return (self.len > 0).then(|| unsafe { zst::<T>() });
+    }
     self.head.as_mut().map(|node| {
       &mut node.elem
     })
   }
 
-  pub fn into_iter(self) -> IntoIter<T> {
-    IntoIter(self)
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn iter(&self) -> Iter<'_, T> {
+    // Iter { next: self.head.as_ref().map(|node| { &**node })}
+    Iter { next: self.head.as_deref(), len: self.len }
+  }
+
+  pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+    IterMut { next: self.head.as_deref_mut(), len: self.len }
+  }
+
+  // 下标从栈顶开始数
+  pub fn get(&self, index: usize) -> Option<&T> {
+    self.iter().nth(index)
+  }
+
+  pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+    self.iter_mut().nth(index)
+  }
+
+  // 第 index 个 Link，index == len 时是末尾的 None
+  fn link_mut(&mut self, index: usize) -> &mut Link<T> {
+    let mut link = &mut self.head;
+    for _ in 0..index {
+      link = &mut link.as_mut().unwrap().next;
+    }
+    link
+  }
+
+  // index 可以等于 len，相当于插到栈底
+  pub fn insert(&mut self, index: usize, elem: T) {
+    assert!(index <= self.len, "insertion index (is {index}) should be <= len (is {})", self.len);
+    if is_zst::<T>() {
+      return self.push(elem);
+    }
+    let link = self.link_mut(index);
+    let next = link.take();
+    *link = Some(Box::new(Node { elem, next }));
+    self.len += 1;
+  }
+
+  pub fn remove(&mut self, index: usize) -> Option<T> {
+    if index >= self.len {
+      return None;
+    }
+    if is_zst::<T>() {
+      return self.pop();
+    }
+    let link = self.link_mut(index);
+    let node = link.take()?;
+    *link = node.next;
+    self.len -= 1;
+    Some(node.elem)
+  }
+
+  pub fn swap(&mut self, i: usize, j: usize) {
+    assert!(i < self.len && j < self.len, "swap index out of bounds: the len is {}", self.len);
+    let (lo, hi) = (i.min(j), i.max(j));
+    if lo == hi {
+      return;
+    }
+    let mut iter = self.iter_mut();
+    let a = iter.nth(lo).unwrap();
+    let b = iter.nth(hi - lo - 1).unwrap();
+    mem::swap(a, b);
   }
+}
+
+impl<T> Index<usize> for List<T> {
+  type Output = T;
+
+  fn index(&self, index: usize) -> &T {
+    let len = self.len;
+    self.get(index).unwrap_or_else(|| panic!("index out of bounds: the len is {len} but the index is {index}"))
+  }
+}
 
-  pub fn iter(&self) -> Iter<T> {
-    // Iter{ next: self.head.as_deref() }
-    Iter { next: self.head.as_ref().map(|node| { &**node })}
+impl<T> IndexMut<usize> for List<T> {
+  fn index_mut(&mut self, index: usize) -> &mut T {
+    let len = self.len;
+    self.get_mut(index).unwrap_or_else(|| panic!("index out of bounds: the len is {len} but the index is {index}"))
   }
+}
 
-  pub fn iter_mut(&mut self) -> IterMut<T> {
-    IterMut { next: self.head.as_deref_mut() }
+impl<T> Default for List<T> {
+  fn default() -> Self {
+    Self::new()
   }
 }
 
+// 逐个 pop 而不是递归地 drop 节点，元素的 drop panic 时也不会退化成递归释放
 impl<T> Drop for List<T> {
   fn drop(&mut self) {
-    let mut cur_link = self.head.take();
-    while let Link::Some(mut node) = cur_link {
-      cur_link = node.next.take();
+    util::pop_all(self, List::pop);
+  }
+}
+
+impl<T> Stack<T> for List<T> {
+  type Peek<'a> = &'a T where T: 'a;
+
+  fn push(&mut self, elem: T) {
+    List::push(self, elem)
+  }
+
+  fn pop(&mut self) -> Option<T> {
+    List::pop(self)
+  }
+
+  fn peek(&self) -> Option<&T> {
+    List::peek(self)
+  }
+
+  fn len(&self) -> usize {
+    List::len(self)
+  }
+}
+
+impl<T: Debug> Debug for List<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_list().entries(self.iter()).finish()
+  }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+  fn eq(&self, other: &Self) -> bool {
+    self.len == other.len && self.iter().eq(other.iter())
+  }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: Hash> Hash for List<T> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    // 先写长度，保证 [a, b] 和 [ab] 之类的拼接不会碰撞
+    state.write_usize(self.len);
+    self.iter().for_each(|elem| elem.hash(state));
+  }
+}
+
+// 用一个指向链尾 Link 的游标按顺序往后接，不递归也不用先收集再反转
+impl<T: Clone> Clone for List<T> {
+  fn clone(&self) -> Self {
+    let mut list = List::new();
+    if is_zst::<T>() {
+      self.iter().for_each(|elem| list.push(elem.clone()));
+      return list;
+    }
+    let mut tail = &mut list.head;
+    for elem in self.iter() {
+      let node = tail.insert(Box::new(Node { elem: elem.clone(), next: None }));
+      tail = &mut node.next;
     }
+    list.len = self.len;
+    list
   }
 }
 
 // impl iterator for List
 pub struct IntoIter<T>(List<T>);
 
+impl<T> IntoIterator for List<T> {
+  type Item = T;
+  type IntoIter = IntoIter<T>;
+
+  fn into_iter(self) -> IntoIter<T> {
+    IntoIter(self)
+  }
+}
+
 impl<T> Iterator for IntoIter<T> {
   type Item = T;
 
   fn next(&mut self) -> Option<Self::Item> {
     self.0.pop()
   }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.0.len, Some(self.0.len))
+  }
 }
 
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
 pub struct Iter<'a, T> {
     next: Option<&'a Node<T>>,
+    len: usize,
 }
 
 impl<'a, T> Iterator for Iter<'a, T> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if is_zst::<T>() {
+            if self.len == 0 {
+                return None;
+            }
+            self.len -= 1;
+            return Some(unsafe { &*zst::<T>() });
+        }
         self.next.map(|node| {
             // self.next = node.next.map(|node| &*node); error,此时 node 中值的所有权被传递到 map 中，这个时候 &*node 返回的话，引用指向一个局部作用域
             self.next = node.next.as_deref();
+            self.len -= 1;
             &node.elem
         })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
 }
 
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
 pub struct IterMut<'a, T> {
-  next: Option<&'a mut Node<T>>
+  next: Option<&'a mut Node<T>>,
+  len: usize,
 }
 
 impl<'a, T> Iterator for IterMut<'a, T> {
   type Item = &'a mut T;
 
   fn next(&mut self) -> Option<Self::Item> {
+    if is_zst::<T>() {
+      if self.len == 0 {
+        return None;
+      }
+      self.len -= 1;
+      return Some(unsafe { zst::<T>() });
+    }
     self.next.take().map(|node| {
       self.next = node.next.as_deref_mut();
+      self.len -= 1;
       &mut node.elem
     })
   }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.len, Some(self.len))
+  }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T: Debug> Visualize for List<T> {
+  // 零大小类型没有节点，图里只有一个指向空的根
+  fn visualize(&self, name: &str, graph: &mut Graph) {
+    let address = |node: &Node<T>| node as *const Node<T> as usize;
+    graph.add_root(name, self.head.as_deref().map(address), None);
+    let mut link = self.head.as_deref();
+    while let Some(node) = link {
+      if !graph.add_node(address(node), format!("{:?}", node.elem), None) {
+        break;
+      }
+      link = node.next.as_deref();
+      if let Some(next) = link {
+        graph.add_edge(address(node), address(next), EdgeKind::Next);
+      }
+    }
+  }
 }
 
 #[cfg(test)]
@@ -131,17 +366,62 @@ mod tests {
     assert_eq!(list.pop(), Some(2));
     assert_eq!(list.pop(), Some(1));
   }
-  
-  // #[test]
-  // fn long_list() {
-  //   let mut list = List::new();
-  //   for i in 0..1000000 {
-  //     list.push(i);
-  //   }
-  //   drop(list);
-  // }
 
+  // 递归的 Drop 会在这里爆栈
   #[test]
+  #[cfg_attr(miri, ignore)]
+  fn long_list() {
+    let mut list = List::new();
+    for i in 0..1000000 {
+      list.push(i);
+    }
+    drop(list);
+  }
+
+  #[test]
+  fn index() {
+    let mut list = List::new();
+    list.push(String::from("d"));
+    list.push(String::from("b"));
+    assert_eq!(list.get(1).map(String::as_str), Some("d"));
+    assert_eq!(list.get(2), None);
+
+    list.insert(0, String::from("a"));
+    list.insert(2, String::from("c"));
+    list.insert(4, String::from("e"));
+    assert_eq!(format!("{:?}", list), r#"["a", "b", "c", "d", "e"]"#);
+    assert_eq!(list.len(), 5);
+
+    list[1].push('!');
+    list.get_mut(4).unwrap().push('?');
+    list.swap(4, 0);
+    list.swap(2, 2);
+    assert_eq!(format!("{:?}", list), r#"["e?", "b!", "c", "d", "a"]"#);
+    assert_eq!(list.remove(4).as_deref(), Some("a"));
+    assert_eq!(list.remove(0).as_deref(), Some("e?"));
+    assert_eq!(list.remove(3), None);
+    assert_eq!((list.len(), list.iter().len()), (3, 3));
+    assert_eq!(list[2], "d");
+
+    // 零大小元素只改 len
+    let mut units = List::new();
+    units.insert(0, ());
+    units.insert(1, ());
+    units.swap(0, 1);
+    assert_eq!((units[1], units.remove(1), units.remove(1)), ((), Some(()), None));
+    assert_eq!(units.len(), 1);
+  }
+
+  #[test]
+  #[should_panic(expected = "index out of bounds: the len is 1 but the index is 1")]
+  fn index_out_of_bounds() {
+    let mut list = List::new();
+    list.push(1);
+    list[1] += 1;
+  }
+
+  #[test]
+  #[allow(clippy::option_map_unit_fn)]
   fn peek() {
     let mut list = List::new();
     assert_eq!(list.peek(), None);
@@ -194,4 +474,26 @@ mod tests {
     assert_eq!(iter_mut.next(), Some(&mut 2));
     assert_eq!(iter_mut.next(), Some(&mut 1));
   }
+
+  #[test]
+  fn len() {
+    let mut list = List::new();
+    assert!(list.is_empty());
+    list.push(1);
+    list.push(2);
+    list.push(3);
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.pop(), Some(3));
+    assert_eq!(list.len(), 2);
+    assert_eq!(list.clone().len(), 2);
+
+    let mut iter = list.iter();
+    assert_eq!(iter.len(), 2);
+    iter.next();
+    assert_eq!(iter.size_hint(), (1, Some(1)));
+    assert_eq!(list.iter_mut().len(), 2);
+    let mut into_iter = list.into_iter();
+    into_iter.next();
+    assert_eq!(into_iter.len(), 1);
+  }
 }
\ No newline at end of file
@@ -0,0 +1,111 @@
+// 测试用的工具：统计分配次数的全局分配器，和 drop 时可以 panic 的元素类型
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::rc::Rc;
+
+// 计数器是线程局部的，并行跑的其它测试不会互相干扰。
+// const 初始化的 thread_local 不需要分配，可以在分配器里用
+thread_local! {
+  static LIVE: Cell<isize> = const { Cell::new(0) };
+}
+
+struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    let _ = LIVE.try_with(|live| live.set(live.get() + 1));
+    System.alloc(layout)
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    let _ = LIVE.try_with(|live| live.set(live.get() - 1));
+    System.dealloc(ptr, layout)
+  }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+// 当前线程还没有释放的分配数（可能是负数：释放了别的线程分配的内存）
+pub fn live_allocations() -> isize {
+  LIVE.with(Cell::get)
+}
+
+// 在 f 里制造一次 panic 并捕获，确认 f 结束之后没有多出来的分配
+pub fn assert_panics_without_leak(f: impl FnOnce()) {
+  // 第一次 unwind 时运行时会初始化一些全局状态，先触发一次
+  let _ = std::panic::catch_unwind(|| std::panic::resume_unwind(Box::new("warm up")));
+  let before = live_allocations();
+  let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+  assert!(result.is_err(), "expected a panic");
+  drop(result);
+  assert_eq!(live_allocations(), before, "allocations leaked");
+}
+
+// drop 时记一次数，panic 为 true 时接着 panic。
+// 用 resume_unwind 而不是 panic!：它不调用 panic hook，
+// RUST_BACKTRACE=1 时 hook 解析符号留下的缓存会被当成泄漏
+pub struct PanicOnDrop {
+  drops: Rc<Cell<usize>>,
+  panic: bool,
+}
+
+impl PanicOnDrop {
+  pub fn new(drops: &Rc<Cell<usize>>, panic: bool) -> Self {
+    PanicOnDrop { drops: drops.clone(), panic }
+  }
+}
+
+impl Drop for PanicOnDrop {
+  fn drop(&mut self) {
+    self.drops.set(self.drops.get() + 1);
+    if self.panic {
+      std::panic::resume_unwind(Box::new("PanicOnDrop"));
+    }
+  }
+}
+
+thread_local! {
+  static LIVE_MARKERS: Cell<isize> = const { Cell::new(0) };
+}
+
+// 零大小、但创建和 drop 都有副作用的标记类型，用来检查零大小元素
+// 既没有多 drop 也没有漏 drop。clone 出来的也算
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct Marker(());
+
+impl Marker {
+  pub fn new() -> Self {
+    LIVE_MARKERS.with(|live| live.set(live.get() + 1));
+    Marker(())
+  }
+}
+
+impl Clone for Marker {
+  fn clone(&self) -> Self {
+    Marker::new()
+  }
+}
+
+impl Drop for Marker {
+  fn drop(&mut self) {
+    LIVE_MARKERS.with(|live| live.set(live.get() - 1));
+  }
+}
+
+// 当前线程上还活着的 Marker 个数
+pub fn live_markers() -> isize {
+  LIVE_MARKERS.with(Cell::get)
+}
+
+// 按页对齐的元素，节点里的 elem 也必须落在 4096 的整数倍上
+#[repr(align(4096))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Page(pub usize);
+
+impl Page {
+  pub fn is_aligned(&self) -> bool {
+    (self as *const Page as usize).is_multiple_of(4096)
+  }
+}
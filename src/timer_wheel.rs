@@ -0,0 +1,405 @@
+// 分层时间轮，时间是一个抽象的 u64 tick，由调用者通过 advance 推进（虚拟时钟）
+// 共 LEVELS 层，每层 64 个槽，第 n 层的一个槽跨越 64^n 个 tick
+// 每个槽是一条由 deque 节点串成的双向链表，TimerHandle 持有节点的 Weak，cancel 时 O(1) 摘下
+// 高层的槽到期时，里面的定时器被重新插入到更低的层（cascade），直到落在第 0 层准确的 tick 上
+// 层的选择和槽的查找参考了 tokio 的时间轮
+
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::deque::{List, Node};
+
+const SLOT_BITS: u32 = 6;
+const SLOTS: usize = 1 << SLOT_BITS;
+const LEVELS: usize = 6;
+// 超过这个距离的定时器先放在最高层，到时候再重新插入
+const MAX_DURATION: u64 = 1 << (SLOT_BITS * LEVELS as u32);
+
+// 用来区分 TimerHandle 属于哪个时间轮
+static NEXT_WHEEL_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Location {
+  Slot(usize, usize),
+  // schedule 时 deadline 已经过去，下一次 advance 立即到期
+  Pending,
+}
+
+struct Timer<T> {
+  deadline: u64,
+  payload: T,
+  location: Location,
+}
+
+type TimerRef<T> = Rc<RefCell<Node<Timer<T>>>>;
+
+pub struct TimerHandle<T> {
+  node: Weak<RefCell<Node<Timer<T>>>>,
+  wheel: u64,
+}
+
+impl<T> Clone for TimerHandle<T> {
+  fn clone(&self) -> Self {
+    TimerHandle { node: self.node.clone(), wheel: self.wheel }
+  }
+}
+
+impl<T> TimerHandle<T> {
+  // 还没有到期也没有被取消
+  pub fn is_active(&self) -> bool {
+    self.node.strong_count() > 0
+  }
+}
+
+struct Level<T> {
+  slots: Vec<List<Timer<T>>>,
+  // 第 i 位表示第 i 个槽非空
+  occupied: u64,
+}
+
+pub struct TimerWheel<T> {
+  levels: Vec<Level<T>>,
+  pending: List<Timer<T>>,
+  elapsed: u64,
+  len: usize,
+  id: u64,
+}
+
+fn slot_range(level: usize) -> u64 {
+  1 << (SLOT_BITS * level as u32)
+}
+
+fn level_range(level: usize) -> u64 {
+  slot_range(level + 1)
+}
+
+// 由 elapsed 和 when 最高的不同位决定层，这样 when 落在当前层的一个 "未来" 的槽里
+fn level_for(elapsed: u64, when: u64) -> usize {
+  let mut masked = (elapsed ^ when) | (SLOTS as u64 - 1);
+  if masked >= MAX_DURATION {
+    masked = MAX_DURATION - 1;
+  }
+  let significant = 63 - masked.leading_zeros();
+  (significant / SLOT_BITS) as usize
+}
+
+fn slot_for(when: u64, level: usize) -> usize {
+  ((when >> (SLOT_BITS * level as u32)) as usize) & (SLOTS - 1)
+}
+
+impl<T> Level<T> {
+  fn new() -> Self {
+    Level { slots: (0..SLOTS).map(|_| List::new()).collect(), occupied: 0 }
+  }
+
+  // 从 now 开始往后找第一个非空的槽，返回它的下标和起始时间
+  fn next_expiration(&self, level: usize, now: u64) -> Option<(usize, u64)> {
+    if self.occupied == 0 {
+      return None;
+    }
+    let now_slot = (now / slot_range(level)) as u32 % SLOTS as u32;
+    let zeros = self.occupied.rotate_right(now_slot).trailing_zeros();
+    let slot = ((zeros + now_slot) % SLOTS as u32) as usize;
+
+    let level_start = now & !(level_range(level) - 1);
+    let mut deadline = level_start + slot as u64 * slot_range(level);
+    if deadline <= now {
+      // 绕回到了下一圈，时钟接近 u64::MAX 时加法会溢出，这时饱和到 u64::MAX
+      deadline = deadline.saturating_add(level_range(level));
+    }
+    Some((slot, deadline))
+  }
+}
+
+impl<T> TimerWheel<T> {
+  pub fn new() -> Self {
+    Self::with_start(0)
+  }
+
+  // 时钟从 start 开始，schedule 的 deadline 都是绝对时间
+  pub fn with_start(start: u64) -> Self {
+    TimerWheel {
+      levels: (0..LEVELS).map(|_| Level::new()).collect(),
+      pending: List::new(),
+      elapsed: start,
+      len: 0,
+      id: NEXT_WHEEL_ID.fetch_add(1, Ordering::Relaxed),
+    }
+  }
+
+  pub fn schedule(&mut self, deadline: u64, payload: T) -> TimerHandle<T> {
+    let node = Node::new(Timer { deadline, payload, location: Location::Pending });
+    let handle = TimerHandle { node: Rc::downgrade(&node), wheel: self.id };
+    self.insert(node);
+    self.len += 1;
+    handle
+  }
+
+  // 已经到期、已经取消或者不属于这个时间轮时返回 None
+  pub fn cancel(&mut self, handle: &TimerHandle<T>) -> Option<T> {
+    if handle.wheel != self.id {
+      return None;
+    }
+    let node = handle.node.upgrade()?;
+    self.unlink(&node);
+    self.len -= 1;
+    Some(Rc::try_unwrap(node).ok().unwrap().into_inner().into_elem().payload)
+  }
+
+  // 把时钟推进到 now，按到期时间的顺序返回所有 deadline <= now 的定时器
+  // 槽和 pending 链表都是从表头插入的，同一批里的顺序是反的，所以最后按 deadline 稳定排序
+  pub fn advance(&mut self, now: u64) -> Vec<T> {
+    let mut expired = Vec::new();
+    while let Some(node) = self.pending.front_node().cloned() {
+      self.pending.unlink_node(&node);
+      expired.push(self.expire(node));
+    }
+
+    while let Some((level, slot, deadline)) = self.next_expiration_slot() {
+      if deadline > now {
+        break;
+      }
+      self.elapsed = deadline;
+      self.levels[level].occupied &= !(1 << slot);
+      let mut timers = std::mem::take(&mut self.levels[level].slots[slot]);
+      while let Some(node) = timers.front_node().cloned() {
+        timers.unlink_node(&node);
+        if RefCell::borrow(&node).elem().deadline <= self.elapsed {
+          expired.push(self.expire(node));
+        } else {
+          self.insert(node);
+        }
+      }
+    }
+
+    self.elapsed = self.elapsed.max(now);
+    expired.sort_by_key(|&(deadline, _)| deadline);
+    expired.into_iter().map(|(_, payload)| payload).collect()
+  }
+
+  // 下一个非空槽的起始时间，advance 到这个时间之前不会有定时器到期
+  pub fn next_expiration(&self) -> Option<u64> {
+    if self.pending.front_node().is_some() {
+      return Some(self.elapsed);
+    }
+    self.next_expiration_slot().map(|(_, _, deadline)| deadline)
+  }
+
+  pub fn elapsed(&self) -> u64 {
+    self.elapsed
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  // 层越低，下一个到期的槽越早，所以找到第一个非空的层就可以停下
+  fn next_expiration_slot(&self) -> Option<(usize, usize, u64)> {
+    self.levels.iter().enumerate().find_map(|(index, level)| {
+      level.next_expiration(index, self.elapsed).map(|(slot, deadline)| (index, slot, deadline))
+    })
+  }
+
+  fn insert(&mut self, node: TimerRef<T>) {
+    let deadline = RefCell::borrow(&node).elem().deadline;
+    if deadline <= self.elapsed {
+      node.borrow_mut().elem_mut().location = Location::Pending;
+      self.pending.push_front_node(node);
+      return;
+    }
+    // 超出范围的定时器按 "范围内最远的时间" 放置，到时候它还没到期，会被重新插入
+    // 如果直接用 deadline 算槽，槽号会绕回来，比范围内真正更早的槽先被处理
+    let when = deadline.min(self.elapsed.saturating_add(MAX_DURATION - 1));
+    let level = level_for(self.elapsed, when);
+    let slot = slot_for(when, level);
+    node.borrow_mut().elem_mut().location = Location::Slot(level, slot);
+    self.levels[level].slots[slot].push_front_node(node);
+    self.levels[level].occupied |= 1 << slot;
+  }
+
+  fn unlink(&mut self, node: &TimerRef<T>) {
+    let location = RefCell::borrow(node).elem().location;
+    match location {
+      Location::Pending => self.pending.unlink_node(node),
+      Location::Slot(level, slot) => {
+        let level = &mut self.levels[level];
+        level.slots[slot].unlink_node(node);
+        if level.slots[slot].front_node().is_none() {
+          level.occupied &= !(1 << slot);
+        }
+      }
+    }
+  }
+
+  fn expire(&mut self, node: TimerRef<T>) -> (u64, T) {
+    self.len -= 1;
+    let timer = Rc::try_unwrap(node).ok().unwrap().into_inner().into_elem();
+    (timer.deadline, timer.payload)
+  }
+}
+
+impl<T> Default for TimerWheel<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn basic() {
+    let mut wheel = TimerWheel::new();
+    wheel.schedule(5, "a");
+    wheel.schedule(3, "b");
+    wheel.schedule(5, "c");
+    assert_eq!(wheel.len(), 3);
+    assert_eq!(wheel.next_expiration(), Some(3));
+
+    assert!(wheel.advance(2).is_empty());
+    assert_eq!(wheel.advance(3), vec!["b"]);
+    let mut fired = wheel.advance(10);
+    fired.sort();
+    assert_eq!(fired, vec!["a", "c"]);
+    assert!(wheel.is_empty());
+    assert_eq!(wheel.elapsed(), 10);
+    assert_eq!(wheel.next_expiration(), None);
+  }
+
+  #[test]
+  fn past_deadline_fires_on_next_advance() {
+    let mut wheel = TimerWheel::with_start(100);
+    wheel.schedule(50, 1);
+    wheel.schedule(100, 2);
+    assert_eq!(wheel.next_expiration(), Some(100));
+    assert_eq!(wheel.advance(100), vec![1, 2]);
+  }
+
+  #[test]
+  fn cascade() {
+    let mut wheel = TimerWheel::new();
+    let deadlines = [64, 65, 4095, 4096, 262_143, 1_000_000, 123_456_789];
+    for &deadline in &deadlines {
+      wheel.schedule(deadline, deadline);
+    }
+    for &deadline in &deadlines {
+      assert!(wheel.advance(deadline - 1).is_empty());
+      assert_eq!(wheel.next_expiration().map(|next| next <= deadline), Some(true));
+      assert_eq!(wheel.advance(deadline), vec![deadline]);
+    }
+    assert!(wheel.is_empty());
+  }
+
+  #[test]
+  fn beyond_horizon() {
+    let mut wheel = TimerWheel::new();
+    let far = MAX_DURATION * 3 + 17;
+    wheel.schedule(far, "far");
+    wheel.schedule(MAX_DURATION - 1, "edge");
+    assert_eq!(wheel.advance(MAX_DURATION - 1), vec!["edge"]);
+    assert!(wheel.advance(far - 1).is_empty());
+    assert_eq!(wheel.advance(far), vec!["far"]);
+  }
+
+  #[test]
+  fn near_u64_max() {
+    let start = u64::MAX - 100;
+    let mut wheel = TimerWheel::with_start(start);
+    wheel.schedule(u64::MAX, "last");
+    wheel.schedule(start + 10, "first");
+    assert!(wheel.advance(start + 9).is_empty());
+    assert_eq!(wheel.advance(start + 10), vec!["first"]);
+    assert!(wheel.next_expiration().is_some());
+    assert_eq!(wheel.advance(u64::MAX), vec!["last"]);
+    assert_eq!(wheel.next_expiration(), None);
+  }
+
+  #[test]
+  fn cancel() {
+    let mut wheel = TimerWheel::new();
+    let a = wheel.schedule(10, "a");
+    let b = wheel.schedule(100_000, "b");
+    let c = wheel.schedule(0, "c");
+    assert!(a.is_active());
+
+    assert_eq!(wheel.cancel(&b), Some("b"));
+    assert_eq!(wheel.cancel(&b), None);
+    assert!(!b.is_active());
+    assert_eq!(wheel.cancel(&c), Some("c"));
+    assert_eq!(wheel.len(), 1);
+
+    let mut other = TimerWheel::new();
+    assert_eq!(other.cancel(&a), None);
+
+    assert_eq!(wheel.advance(1_000_000), vec!["a"]);
+    assert_eq!(wheel.cancel(&a), None);
+    assert!(!a.is_active());
+  }
+
+  #[test]
+  fn cancel_clears_slot() {
+    let mut wheel = TimerWheel::new();
+    let handle = wheel.schedule(5000, ());
+    wheel.schedule(70_000, ());
+    assert_eq!(wheel.next_expiration(), Some(4096));
+    wheel.cancel(&handle);
+    // 5000 所在的槽已经空了，下一个到期的是 70000 所在的槽
+    assert_eq!(wheel.next_expiration(), Some(17 * 4096));
+    assert_eq!(wheel.advance(70_000).len(), 1);
+  }
+
+  // 和暴力模型对比：每次 advance 返回的正好是 (上次时间, now] 内到期的定时器，且按 deadline 排好序
+  #[test]
+  fn virtual_clock_model() {
+    let mut x = 0x853c_49e6_748f_ea9bu64;
+    let mut rand = move |n: u64| {
+      x ^= x << 13;
+      x ^= x >> 7;
+      x ^= x << 17;
+      x % n
+    };
+
+    let mut wheel = TimerWheel::new();
+    let mut model: Vec<(u64, usize)> = Vec::new();
+    let mut handles = Vec::new();
+    let mut now = 0;
+    for id in 0..if cfg!(miri) { 300 } else { 3000 } {
+      let spread = [10, 1000, 100_000, 10_000_000, 1 << 40][rand(5) as usize];
+      let deadline = now + rand(spread);
+      handles.push(wheel.schedule(deadline, (deadline, id)));
+      model.push((deadline, id));
+
+      if rand(5) == 0 {
+        let victim = rand(handles.len() as u64) as usize;
+        let cancelled = wheel.cancel(&handles[victim]);
+        let pos = model.iter().position(|&(_, id)| id == victim);
+        assert_eq!(cancelled.map(|(_, id)| id), pos.map(|pos| model.remove(pos).1));
+      }
+
+      if rand(3) == 0 {
+        let step = [5, 500, 50_000][rand(3) as usize];
+        now += rand(step);
+        let fired = wheel.advance(now);
+        let mut expected: Vec<_> = model.iter().copied().filter(|&(deadline, _)| deadline <= now).collect();
+        model.retain(|&(deadline, _)| deadline > now);
+        expected.sort();
+
+        assert!(fired.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+        let mut fired = fired;
+        fired.sort();
+        assert_eq!(fired, expected);
+        assert_eq!(wheel.len(), model.len());
+      }
+    }
+
+    let fired = wheel.advance(u64::MAX / 2);
+    assert_eq!(fired.len(), model.len());
+    assert!(wheel.is_empty());
+  }
+}
@@ -0,0 +1,247 @@
+// 各个模块的 List 方法名各不相同：push/pop、push_front/pop_back、prepend/tail。
+// 这里定义统一的接口，泛型代码只依赖这些 trait，换实现只需要改一个类型别名
+//
+// peek 的返回值用 GAT 表示：普通的链表直接返回 &T，deque 只能返回 Ref<T>，
+// 两者都能 Deref 到 T
+//
+// 实现情况：
+//   Stack: stack_bad（只能存 i32）、stack_ok、unsafe_deque_ok、deque
+//   Queue/Deque: unsafe_deque_ok、deque
+//   PersistentStack: persistent_stack、finger_tree
+//   PersistentQueue: persistent_queue 的 PersistentQueue 和 RealTimeQueue
+// 下面几个故意不实现：
+//   BoundedQueue 的 push 会失败或者挤掉旧元素，返回值是 Result<Option<T>, T>，
+//   而且构造时必须给出容量，没有合理的 Default，套进 Queue 会把这些语义藏起来
+//   safe_deque_bad 的 push/pop 要求 &'a mut self，调用一次之后链表就一直被借用着，写不出 trait 方法
+//   unsafe_deque_bad 是违反 Stacked Borrows 的反面例子，只留着给 Miri 演示，不应该被泛型代码拿来用
+
+use std::ops::Deref;
+
+// 后进先出
+pub trait Stack<T>: Default {
+  type Peek<'a>: Deref<Target = T>
+  where
+    Self: 'a;
+
+  fn push(&mut self, elem: T);
+  fn pop(&mut self) -> Option<T>;
+  fn peek(&self) -> Option<Self::Peek<'_>>;
+  fn len(&self) -> usize;
+
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+// 先进先出，从 back 进、从 front 出
+pub trait Queue<T>: Default {
+  type Peek<'a>: Deref<Target = T>
+  where
+    Self: 'a;
+
+  fn push_back(&mut self, elem: T);
+  fn pop_front(&mut self) -> Option<T>;
+  fn peek_front(&self) -> Option<Self::Peek<'_>>;
+  fn len(&self) -> usize;
+
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+// 两端都能进出
+pub trait Deque<T>: Queue<T> {
+  fn push_front(&mut self, elem: T);
+  fn pop_back(&mut self) -> Option<T>;
+  fn peek_back(&self) -> Option<Self::Peek<'_>>;
+}
+
+// 不可变的栈，每个操作返回新版本，旧版本保持不变
+pub trait PersistentStack<T>: Clone + Default {
+  fn push(&self, elem: T) -> Self;
+  // 栈顶元素和弹出之后的新版本
+  fn pop(&self) -> Option<(&T, Self)>;
+  fn peek(&self) -> Option<&T>;
+  fn len(&self) -> usize;
+
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+// 不可变的队列，从 back 进、从 front 出
+pub trait PersistentQueue<T>: Clone + Default {
+  fn push_back(&self, elem: T) -> Self;
+  // 队首元素和出队之后的新版本
+  fn pop_front(&self) -> Option<(&T, Self)>;
+  fn peek_front(&self) -> Option<&T>;
+  fn len(&self) -> usize;
+
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+// 下面是只依赖 trait 的算法
+
+// 括号是否配对
+pub fn balanced<S: Stack<char>>(text: &str) -> bool {
+  let mut stack = S::default();
+  for c in text.chars() {
+    match c {
+      '(' | '[' | '{' => stack.push(c),
+      ')' | ']' | '}' => {
+        let open = match c {
+          ')' => '(',
+          ']' => '[',
+          _ => '{',
+        };
+        if stack.pop() != Some(open) {
+          return false;
+        }
+      },
+      _ => {}
+    }
+  }
+  stack.is_empty()
+}
+
+// 约瑟夫问题：n 个人围成一圈，每数到第 k 个出列，返回出列顺序
+pub fn josephus<Q: Queue<usize>>(n: usize, k: usize) -> Vec<usize> {
+  assert!(k > 0, "k must be greater than zero");
+  let mut queue = Q::default();
+  (0..n).for_each(|i| queue.push_back(i));
+  let mut order = Vec::with_capacity(n);
+  while !queue.is_empty() {
+    for _ in 1..k {
+      let skipped = queue.pop_front().unwrap();
+      queue.push_back(skipped);
+    }
+    order.push(queue.pop_front().unwrap());
+  }
+  order
+}
+
+// 滑动窗口最大值，单调队列里存下标，对应的值从前往后递减
+pub fn sliding_window_max<D: Deque<usize>>(values: &[i32], k: usize) -> Vec<i32> {
+  assert!(k > 0, "window size must be greater than zero");
+  let mut window = D::default();
+  let mut result = Vec::new();
+  for (i, &value) in values.iter().enumerate() {
+    while window.peek_back().is_some_and(|back| values[*back] <= value) {
+      window.pop_back();
+    }
+    window.push_back(i);
+    if window.peek_front().is_some_and(|front| *front + k <= i) {
+      window.pop_front();
+    }
+    if i + 1 >= k {
+      result.push(values[*window.peek_front().unwrap()]);
+    }
+  }
+  result
+}
+
+// 依次压入 items，返回每一步的版本（包括一开始的空栈），它们之间共享节点
+pub fn history<P: PersistentStack<T>, T>(items: impl IntoIterator<Item = T>) -> Vec<P> {
+  let mut versions = vec![P::default()];
+  for item in items {
+    let next = versions.last().unwrap().push(item);
+    versions.push(next);
+  }
+  versions
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{deque, finger_tree, persistent_queue, persistent_stack, stack_bad, stack_ok};
+  use crate::unsafe_deque_ok::unsafe_deque_ok;
+
+  // 换一个实现只需要改这一行
+  type CharStack = stack_ok::List<char>;
+
+  #[test]
+  fn stacks() {
+    for (text, expected) in [("", true), ("([]{()})", true), ("a(b)c", true), ("(]", false), ("((", false), ("())", false)] {
+      assert_eq!(balanced::<CharStack>(text), expected, "{}", text);
+      assert_eq!(balanced::<unsafe_deque_ok::List<char>>(text), expected, "{}", text);
+      assert_eq!(balanced::<deque::List<char>>(text), expected, "{}", text);
+    }
+
+    // stack_bad 只能存 i32
+    fn drain<S: Stack<i32>>(mut stack: S) -> Vec<i32> {
+      assert_eq!(stack.peek().map(|top| *top), Some(3));
+      assert_eq!(stack.len(), 3);
+      std::iter::from_fn(|| stack.pop()).collect()
+    }
+    fn filled<S: Stack<i32>>() -> S {
+      let mut stack = S::default();
+      (1..=3).for_each(|i| stack.push(i));
+      stack
+    }
+    let expected = vec![3, 2, 1];
+    assert_eq!(drain(filled::<stack_bad::List>()), expected);
+    assert_eq!(drain(filled::<stack_ok::List<i32>>()), expected);
+    assert_eq!(drain(filled::<unsafe_deque_ok::List<i32>>()), expected);
+    assert_eq!(drain(filled::<deque::List<i32>>()), expected);
+  }
+
+  #[test]
+  fn queues() {
+    let expected = vec![2, 5, 1, 6, 4, 0, 3];
+    assert_eq!(josephus::<unsafe_deque_ok::List<usize>>(7, 3), expected);
+    assert_eq!(josephus::<deque::List<usize>>(7, 3), expected);
+    assert!(josephus::<deque::List<usize>>(0, 3).is_empty());
+  }
+
+  #[test]
+  fn deques() {
+    let values = [1, 3, -1, -3, 5, 3, 6, 7];
+    let expected = vec![3, 3, 5, 5, 6, 7];
+    assert_eq!(sliding_window_max::<unsafe_deque_ok::List<usize>>(&values, 3), expected);
+    assert_eq!(sliding_window_max::<deque::List<usize>>(&values, 3), expected);
+    assert_eq!(sliding_window_max::<deque::List<usize>>(&values, 1), values.to_vec());
+  }
+
+  #[test]
+  fn persistent_stacks() {
+    fn check<P: PersistentStack<i32>>() {
+      let versions: Vec<P> = history(1..=3);
+      assert_eq!(versions.len(), 4);
+      assert!(versions[0].is_empty());
+      assert_eq!(versions[3].peek(), Some(&3));
+      assert_eq!(versions[3].len(), 3);
+
+      let (top, rest) = versions[3].pop().unwrap();
+      assert_eq!(top, &3);
+      assert_eq!(rest.peek(), Some(&2));
+      // 旧版本不受影响
+      assert_eq!(versions[2].len(), 2);
+      assert!(versions[0].pop().is_none());
+    }
+    check::<persistent_stack::List<i32>>();
+    check::<finger_tree::FingerTree<i32>>();
+
+    let versions: Vec<persistent_stack::List<i32>> = history(1..=3);
+    assert_eq!(versions[3].shares_tail_with(&versions[2]), 2);
+  }
+
+  #[test]
+  fn persistent_queues() {
+    fn check<Q: PersistentQueue<i32>>() {
+      let queue = (1..=3).fold(Q::default(), |queue, i| queue.push_back(i));
+      assert_eq!(queue.len(), 3);
+      assert_eq!(queue.peek_front(), Some(&1));
+
+      let (front, rest) = queue.pop_front().unwrap();
+      assert_eq!(front, &1);
+      assert_eq!(rest.push_back(4).peek_front(), Some(&2));
+      // 旧版本不受影响
+      assert_eq!(queue.len(), 3);
+      assert!(Q::default().pop_front().is_none());
+    }
+    check::<persistent_queue::PersistentQueue<i32>>();
+    check::<persistent_queue::RealTimeQueue<i32>>();
+  }
+}
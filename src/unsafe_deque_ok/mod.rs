@@ -1,3 +1,4 @@
 pub mod safe_deque_bad;
 pub mod unsafe_deque_bad;
+#[allow(clippy::module_inception)]
 pub mod unsafe_deque_ok;
\ No newline at end of file
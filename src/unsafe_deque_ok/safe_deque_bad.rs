@@ -42,6 +42,12 @@ pub struct Node<T> {
   next: Link<T>
 }
 
+impl<T> Default for List<'_, T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl<'a, T> List<'a, T> {
   pub fn new() -> Self {
     Self { head: None, tail: None }
@@ -12,6 +12,12 @@ pub struct Node<T> {
   next: Link<T>
 }
 
+impl<T> Default for List<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl<T> List<T> {
   pub fn new() -> Self {
     Self { head: None, tail: ptr::null_mut() }
@@ -45,7 +51,10 @@ impl<T> List<T> {
 mod tests {
   use super::*;
 
+  // push 故意违反 Stacked Borrows，Miri 下默认跳过，
+  // 用 cargo +nightly miri test unsafe_deque_bad -- --ignored 查看 Miri 的报告
   #[test]
+  #[cfg_attr(miri, ignore)]
   fn basic() {
     let mut list = List::new();
     assert_eq!(list.pop(), None);
@@ -1,122 +1,579 @@
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Index, IndexMut};
+use std::ptr::{self, NonNull};
+
+use crate::debug_viz::{EdgeKind, Graph, Visualize};
+use crate::traits::{Deque, Queue, Stack};
+use crate::util::{self, is_zst, zst};
+
+// 用 Option<NonNull<_>> 代替 *mut：
+// - *mut T 对 T 是不变（invariant）的，NonNull<T> 是协变的，List<&'static str> 可以当成 List<&'a str> 用
+// - PhantomData<Box<Node<T>>> 告诉编译器 List 拥有 Node<T>，drop check 会把 T 也考虑进来
+// - 裸指针不会自动实现 Send/Sync，要手动按 Box 的规则写出来
+// - T 是零大小类型时不分配节点，head/tail 一直是 None，只靠 len 记数
+//
+/// 链式的 FIFO 队列。
+///
+/// 和 `Box` 一样，`T: Send` 时才是 `Send`，`T: Sync` 时才是 `Sync`：
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<linkedlist_rs::unsafe_deque_ok::unsafe_deque_ok::List<std::rc::Rc<i32>>>();
+/// ```
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<linkedlist_rs::unsafe_deque_ok::unsafe_deque_ok::List<std::cell::Cell<i32>>>();
+/// ```
+///
+/// `IterMut` 对 `T` 是不变的，否则可以借它把短生命周期的引用写进长生命周期的链表：
+///
+/// ```compile_fail
+/// use linkedlist_rs::unsafe_deque_ok::unsafe_deque_ok::IterMut;
+/// fn shorten<'i, 'a>(iter: IterMut<'i, &'static str>) -> IterMut<'i, &'a str> {
+///   iter
+/// }
+/// ```
 pub struct List<T> {
   head: Link<T>,
-  tail: Link<T>
+  tail: Link<T>,
+  len: usize,
+  _boo: PhantomData<Box<Node<T>>>,
 }
 
-pub type Link<T> = *mut Node<T>;
+pub type Link<T> = Option<NonNull<Node<T>>>;
 
+// prev 给 pop_back 和 Iter 的 next_back 用
 pub struct Node<T> {
   elem: T,
+  prev: Link<T>,
   next: Link<T>,
 }
 
+// SAFETY: List 独占它的所有节点，裸指针从不外泄到链表外面，
+// 把链表移到别的线程就和移动 Box<Node<T>> 一样，只要求 T: Send
+unsafe impl<T: Send> Send for List<T> {}
+// SAFETY: &List 只能拿到 &T（peek、iter），从不经由共享引用改动节点，
+// 多个线程同时持有 &List 和同时持有 &T 一样，只要求 T: Sync
+unsafe impl<T: Sync> Sync for List<T> {}
+
+// SAFETY: Iter 只产出 &'a T，效果等同于把 &T 送到别的线程，要求 T: Sync
+unsafe impl<T: Sync> Send for Iter<'_, T> {}
+// SAFETY: &Iter 不能推进迭代器，也拿不到任何元素，共享它比共享 &T 还弱
+unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+// SAFETY: IterMut 独占地借用链表并产出互不重叠的 &'a mut T，
+// 效果等同于把 &mut T 送到别的线程，要求 T: Send
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+// SAFETY: &IterMut 不能推进迭代器，也拿不到任何元素，共享它最多相当于共享 &T
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+impl<T> Default for List<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl<T> List<T> {
   pub fn new() -> Self {
-    List { head: std::ptr::null_mut(), tail: std::ptr::null_mut() }
+    List { head: None, tail: None, len: 0, _boo: PhantomData }
   }
 
   pub fn push(&mut self, elem: T) {
-    let new_tail = Box::into_raw(Box::new(Node { elem, next: std::ptr::null_mut() }));
+    if is_zst::<T>() {
+      mem::forget(elem);
+      self.len += 1;
+      return;
+    }
+    let new_tail = NonNull::from(Box::leak(Box::new(Node { elem, prev: self.tail, next: None })));
 
-    if !self.head.is_null() {
-      unsafe { (*self.tail).next = new_tail };
-    } else {
-      self.head = new_tail;
+    match self.tail {
+      Some(old_tail) => unsafe { (*old_tail.as_ptr()).next = Some(new_tail) },
+      None => self.head = Some(new_tail),
     }
 
-    self.tail = new_tail;
+    self.tail = Some(new_tail);
+    self.len += 1;
   }
 
   pub fn pop(&mut self) -> Option<T> {
-    if self.head.is_null() {
-      None
-    } else {
-      let old_head = unsafe { Box::from_raw(self.head) };
+    if is_zst::<T>() {
+      if self.len == 0 {
+        return None;
+      }
+      self.len -= 1;
+      return Some(unsafe { ptr::read(zst::<T>()) });
+    }
+    self.head.map(|old_head| {
+      let old_head = unsafe { Box::from_raw(old_head.as_ptr()) };
       self.head = old_head.next;
 
-      if self.head.is_null() {
-        self.tail = std::ptr::null_mut();
+      match self.head {
+        Some(new_head) => unsafe { (*new_head.as_ptr()).prev = None },
+        None => self.tail = None,
       }
-      Some(old_head.elem)
-    }
+      self.len -= 1;
+      old_head.elem
+    })
   }
 
   pub fn peek(&self) -> Option<&T> {
-    // if self.head.is_null() {
-    //   None
-    // } else {
-    //   Some(unsafe { &(*self.head).elem })
-    // }
-    unsafe { self.head.as_ref().map(|node| &node.elem) }
+    if is_zst::<T>() {
+      return (self.len > 0).then(|| unsafe { &*zst::<T>() });
+    }
+    unsafe { self.head.map(|node| &(*node.as_ptr()).elem) }
   }
 
   pub fn peek_mut(&mut self) -> Option<&mut T> {
-    unsafe { self.head.as_mut().map(|node| &mut node.elem) }
+    if is_zst::<T>() {
+      return (self.len > 0).then(|| unsafe { zst::<T>() });
+    }
+    unsafe { self.head.map(|node| &mut (*node.as_ptr()).elem) }
+  }
+
+  pub fn push_front(&mut self, elem: T) {
+    if is_zst::<T>() {
+      mem::forget(elem);
+      self.len += 1;
+      return;
+    }
+    let new_head = NonNull::from(Box::leak(Box::new(Node { elem, prev: None, next: self.head })));
+
+    match self.head {
+      Some(old_head) => unsafe { (*old_head.as_ptr()).prev = Some(new_head) },
+      None => self.tail = Some(new_head),
+    }
+
+    self.head = Some(new_head);
+    self.len += 1;
+  }
+
+  pub fn pop_back(&mut self) -> Option<T> {
+    if is_zst::<T>() {
+      if self.len == 0 {
+        return None;
+      }
+      self.len -= 1;
+      return Some(unsafe { ptr::read(zst::<T>()) });
+    }
+    self.tail.map(|old_tail| {
+      let old_tail = unsafe { Box::from_raw(old_tail.as_ptr()) };
+      self.tail = old_tail.prev;
+
+      match self.tail {
+        Some(new_tail) => unsafe { (*new_tail.as_ptr()).next = None },
+        None => self.head = None,
+      }
+      self.len -= 1;
+      old_tail.elem
+    })
+  }
+
+  pub fn peek_back(&self) -> Option<&T> {
+    if is_zst::<T>() {
+      return (self.len > 0).then(|| unsafe { &*zst::<T>() });
+    }
+    unsafe { self.tail.map(|node| &(*node.as_ptr()).elem) }
+  }
+
+  pub fn peek_back_mut(&mut self) -> Option<&mut T> {
+    if is_zst::<T>() {
+      return (self.len > 0).then(|| unsafe { zst::<T>() });
+    }
+    unsafe { self.tail.map(|node| &mut (*node.as_ptr()).elem) }
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  // 第 index 个节点，从离它近的一端走过去。零大小类型没有节点，返回 None
+  fn node(&self, index: usize) -> Link<T> {
+    if index >= self.len {
+      return None;
+    }
+    unsafe {
+      if index < self.len - index {
+        let mut node = self.head?;
+        for _ in 0..index {
+          node = (*node.as_ptr()).next?;
+        }
+        Some(node)
+      } else {
+        let mut node = self.tail?;
+        for _ in index + 1..self.len {
+          node = (*node.as_ptr()).prev?;
+        }
+        Some(node)
+      }
+    }
+  }
+
+  pub fn get(&self, index: usize) -> Option<&T> {
+    if is_zst::<T>() {
+      return (index < self.len).then(|| unsafe { &*zst::<T>() });
+    }
+    unsafe { self.node(index).map(|node| &(*node.as_ptr()).elem) }
+  }
+
+  pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+    if is_zst::<T>() {
+      return (index < self.len).then(|| unsafe { zst::<T>() });
+    }
+    unsafe { self.node(index).map(|node| &mut (*node.as_ptr()).elem) }
+  }
+
+  // 新元素放到第 index 个位置上，index 可以等于 len
+  pub fn insert(&mut self, index: usize, elem: T) {
+    assert!(index <= self.len, "insertion index (is {index}) should be <= len (is {})", self.len);
+    if index == 0 {
+      return self.push_front(elem);
+    }
+    if index == self.len || is_zst::<T>() {
+      return self.push(elem);
+    }
+    // 两端都处理过了，next 前后都有节点
+    let next = self.node(index).unwrap();
+    unsafe {
+      let prev = (*next.as_ptr()).prev.unwrap();
+      let node = NonNull::from(Box::leak(Box::new(Node { elem, prev: Some(prev), next: Some(next) })));
+      (*prev.as_ptr()).next = Some(node);
+      (*next.as_ptr()).prev = Some(node);
+    }
+    self.len += 1;
+  }
+
+  pub fn remove(&mut self, index: usize) -> Option<T> {
+    if index >= self.len {
+      return None;
+    }
+    if index == 0 || is_zst::<T>() {
+      return self.pop();
+    }
+    if index == self.len - 1 {
+      return self.pop_back();
+    }
+    let node = unsafe { Box::from_raw(self.node(index)?.as_ptr()) };
+    let (prev, next) = (node.prev.unwrap(), node.next.unwrap());
+    unsafe {
+      (*prev.as_ptr()).next = Some(next);
+      (*next.as_ptr()).prev = Some(prev);
+    }
+    self.len -= 1;
+    Some(node.elem)
+  }
+
+  pub fn swap(&mut self, i: usize, j: usize) {
+    assert!(i < self.len && j < self.len, "swap index out of bounds: the len is {}", self.len);
+    if i == j || is_zst::<T>() {
+      return;
+    }
+    let (a, b) = (self.node(i).unwrap(), self.node(j).unwrap());
+    // 两个不同的节点，elem 不会重叠
+    unsafe { ptr::swap(&mut (*a.as_ptr()).elem, &mut (*b.as_ptr()).elem) }
+  }
+}
+
+impl<T> Index<usize> for List<T> {
+  type Output = T;
+
+  fn index(&self, index: usize) -> &T {
+    let len = self.len;
+    self.get(index).unwrap_or_else(|| panic!("index out of bounds: the len is {len} but the index is {index}"))
+  }
+}
+
+impl<T> IndexMut<usize> for List<T> {
+  fn index_mut(&mut self, index: usize) -> &mut T {
+    let len = self.len;
+    self.get_mut(index).unwrap_or_else(|| panic!("index out of bounds: the len is {len} but the index is {index}"))
   }
 }
 
 pub struct IntoIter<T>(List<T>);
 
+impl<T> IntoIterator for List<T> {
+  type Item = T;
+  type IntoIter = IntoIter<T>;
+
+  fn into_iter(self) -> IntoIter<T> {
+    IntoIter(self)
+  }
+}
+
 impl<T> Iterator for IntoIter<T> {
   type Item = T;
 
   fn next(&mut self) -> Option<Self::Item> {
     self.0.pop()
   }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.0.len, Some(self.0.len))
+  }
 }
 
+// 迭代器只持有裸指针，生命周期和可变性由 PhantomData 表达。
+// 两端各有一个指针，靠 len 判断什么时候相遇，所以不会把同一个元素交出去两次
 pub struct Iter<'a, T> {
-  next: Option<&'a Node<T>>
+  front: Link<T>,
+  back: Link<T>,
+  len: usize,
+  _boo: PhantomData<&'a T>,
 }
 
 impl<'a, T> Iterator for Iter<'a, T> {
   type Item = &'a T;
 
   fn next(&mut self) -> Option<Self::Item> {
-    self.next.take().map(|old_head| {
-      self.next = unsafe { old_head.next.as_ref() };
+    if self.len == 0 {
+      return None;
+    }
+    if is_zst::<T>() {
+      self.len -= 1;
+      return Some(unsafe { &*zst::<T>() });
+    }
+    self.front.map(|node| unsafe {
+      self.len -= 1;
+      self.front = (*node.as_ptr()).next;
       // NOTE: 这个地方的 &old_head.elem 的返回值生命周期是什么？
-      &old_head.elem
+      // 是 'a：它来自裸指针，生命周期完全由 PhantomData<&'a T> 决定
+      &(*node.as_ptr()).elem
+    })
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.len, Some(self.len))
+  }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    if self.len == 0 {
+      return None;
+    }
+    if is_zst::<T>() {
+      self.len -= 1;
+      return Some(unsafe { &*zst::<T>() });
+    }
+    self.back.map(|node| unsafe {
+      self.len -= 1;
+      self.back = (*node.as_ptr()).prev;
+      &(*node.as_ptr()).elem
     })
   }
 }
 
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+// 共享迭代器可以随便复制，不要求 T: Clone
+impl<T> Clone for Iter<'_, T> {
+  fn clone(&self) -> Self {
+    Iter { ..*self }
+  }
+}
+
 pub struct IterMut<'a, T> {
-  next: Option<&'a mut Node<T>>
+  front: Link<T>,
+  back: Link<T>,
+  len: usize,
+  _boo: PhantomData<&'a mut T>,
 }
 
 impl<'a, T> Iterator for IterMut<'a, T> {
   type Item = &'a mut T;
 
   fn next(&mut self) -> Option<Self::Item> {
-    self.next.take().map(|old_head| {
-      self.next = unsafe { old_head.next.as_mut() };
-      &mut old_head.elem
+    if self.len == 0 {
+      return None;
+    }
+    if is_zst::<T>() {
+      self.len -= 1;
+      return Some(unsafe { zst::<T>() });
+    }
+    self.front.map(|node| unsafe {
+      self.len -= 1;
+      self.front = (*node.as_ptr()).next;
+      &mut (*node.as_ptr()).elem
+    })
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.len, Some(self.len))
+  }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    if self.len == 0 {
+      return None;
+    }
+    if is_zst::<T>() {
+      self.len -= 1;
+      return Some(unsafe { zst::<T>() });
+    }
+    self.back.map(|node| unsafe {
+      self.len -= 1;
+      self.back = (*node.as_ptr()).prev;
+      &mut (*node.as_ptr()).elem
     })
   }
 }
 
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
 impl<T> List<T> {
-  pub fn into_iter(self) -> IntoIter<T> {
-    IntoIter(self)
+  pub fn iter(&self) -> Iter<'_, T> {
+    Iter { front: self.head, back: self.tail, len: self.len, _boo: PhantomData }
+  }
+
+  pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+    IterMut { front: self.head, back: self.tail, len: self.len, _boo: PhantomData }
+  }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+  type Item = &'a T;
+  type IntoIter = Iter<'a, T>;
+
+  fn into_iter(self) -> Iter<'a, T> {
+    self.iter()
+  }
+}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+  type Item = &'a mut T;
+  type IntoIter = IterMut<'a, T>;
+
+  fn into_iter(self) -> IterMut<'a, T> {
+    self.iter_mut()
+  }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+// 当作栈用时在 back 一端进出
+impl<T> Stack<T> for List<T> {
+  type Peek<'a> = &'a T where T: 'a;
+
+  fn push(&mut self, elem: T) {
+    List::push(self, elem)
+  }
+
+  fn pop(&mut self) -> Option<T> {
+    List::pop_back(self)
   }
 
-  pub fn iter(&mut self) -> Iter<T> {
-    Iter { next: unsafe { self.head.as_ref() } }
+  fn peek(&self) -> Option<&T> {
+    List::peek_back(self)
   }
 
-  pub fn iter_mut(&mut self) -> IterMut<T> {
-    IterMut { next: unsafe { self.head.as_mut() } }
+  fn len(&self) -> usize {
+    List::len(self)
   }
 }
 
-impl<T> IntoIter<T> {
-  
+impl<T> Queue<T> for List<T> {
+  type Peek<'a> = &'a T where T: 'a;
+
+  fn push_back(&mut self, elem: T) {
+    List::push(self, elem)
+  }
+
+  fn pop_front(&mut self) -> Option<T> {
+    List::pop(self)
+  }
+
+  fn peek_front(&self) -> Option<&T> {
+    List::peek(self)
+  }
+
+  fn len(&self) -> usize {
+    List::len(self)
+  }
+}
+
+impl<T> Deque<T> for List<T> {
+  fn push_front(&mut self, elem: T) {
+    List::push_front(self, elem)
+  }
+
+  fn pop_back(&mut self) -> Option<T> {
+    List::pop_back(self)
+  }
+
+  fn peek_back(&self) -> Option<&T> {
+    List::peek_back(self)
+  }
+}
+
+impl<T: Debug> Debug for List<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_list().entries(self.iter()).finish()
+  }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+  fn eq(&self, other: &Self) -> bool {
+    self.len == other.len && self.iter().eq(other.iter())
+  }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: Hash> Hash for List<T> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    state.write_usize(self.len);
+    self.iter().for_each(|elem| elem.hash(state));
+  }
+}
+
+impl<T: Clone> Clone for List<T> {
+  fn clone(&self) -> Self {
+    let mut list = List::new();
+    self.iter().for_each(|elem| list.push(elem.clone()));
+    list
+  }
 }
 
+// 元素的 drop panic 时也要接着 pop，否则剩下的节点只有裸指针指着，没有人会释放它们。
+// pop 在交出元素之前已经把 head/tail/len 改好了，所以 panic 时链表的状态是一致的
 impl<T> Drop for List<T> {
   fn drop(&mut self) {
-    while let Some(_) = self.pop() {}
+    util::pop_all(self, List::pop);
+  }
+}
+
+impl<T: Debug> Visualize for List<T> {
+  // 零大小类型没有节点，图里只有一个指向空的根
+  fn visualize(&self, name: &str, graph: &mut Graph) {
+    let address = |node: NonNull<Node<T>>| node.as_ptr() as usize;
+    graph.add_root(name, self.head.map(address), self.tail.map(address));
+    let mut current = self.head;
+    while let Some(node) = current {
+      let inner = unsafe { node.as_ref() };
+      if !graph.add_node(address(node), format!("{:?}", inner.elem), None) {
+        break;
+      }
+      if let Some(prev) = inner.prev {
+        graph.add_edge(address(node), address(prev), EdgeKind::Prev);
+      }
+      if let Some(next) = inner.next {
+        graph.add_edge(address(node), address(next), EdgeKind::Next);
+      }
+      current = inner.next;
+    }
   }
 }
 
@@ -137,6 +594,35 @@ mod tests {
     assert_eq!(list.pop(), Some(2));
   }
 
+  #[test]
+  fn both_ends() {
+    let mut list = List::new();
+    assert_eq!(list.pop_back(), None);
+    assert_eq!(list.peek_back(), None);
+
+    list.push_front(2);
+    list.push(3);
+    list.push_front(1);
+    assert_eq!(list.peek_back(), Some(&3));
+    *list.peek_back_mut().unwrap() = 30;
+    assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 30]);
+    assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), vec![30, 2, 1]);
+
+    assert_eq!(list.pop_back(), Some(30));
+    assert_eq!(list.pop(), Some(1));
+    assert_eq!(list.pop_back(), Some(2));
+    assert_eq!(list.len(), 0);
+    assert_eq!(list.peek(), None);
+
+    // 清空之后两端都要能重新开始
+    list.push_front(4);
+    list.push(5);
+    assert_eq!(list.pop_back(), Some(5));
+    assert_eq!(list.pop_back(), Some(4));
+    list.push(6);
+    assert_eq!(list.pop(), Some(6));
+  }
+
   #[test]
   fn iter() {
     let mut list = List::new();
@@ -161,4 +647,200 @@ mod tests {
     assert_eq!(intoiter.next(), Some(2));
     assert_eq!(intoiter.next(), Some(3));
   }
+
+  #[test]
+  fn index() {
+    let mut list = List::new();
+    for i in 0..10 {
+      list.push(i);
+    }
+    // 前半段从 head 走，后半段从 tail 走
+    assert_eq!((0..11).map(|i| list.get(i).copied()).collect::<Vec<_>>(),
+               (0..10).map(Some).chain([None]).collect::<Vec<_>>());
+
+    list.insert(0, -1);
+    list.insert(11, 10);
+    list.insert(3, 100);
+    list.insert(9, 200);
+    assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![-1, 0, 1, 100, 2, 3, 4, 5, 6, 200, 7, 8, 9, 10]);
+    // prev 指针也要接好
+    assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), vec![10, 9, 8, 7, 200, 6, 5, 4, 3, 2, 100, 1, 0, -1]);
+
+    list[1] = 50;
+    *list.get_mut(12).unwrap() += 1;
+    list.swap(1, 12);
+    list.swap(3, 3);
+    assert_eq!((list[1], list[12]), (10, 50));
+    assert_eq!(list.remove(9), Some(200));
+    assert_eq!(list.remove(3), Some(100));
+    assert_eq!(list.remove(0), Some(-1));
+    assert_eq!(list.remove(10), Some(10));
+    assert_eq!(list.remove(10), None);
+    assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![10, 1, 2, 3, 4, 5, 6, 7, 8, 50]);
+    assert_eq!(list.iter().rev().len(), 10);
+    assert_eq!((list.peek(), list.peek_back()), (Some(&10), Some(&50)));
+
+    // 零大小元素只改 len
+    let mut units = List::new();
+    units.insert(0, ());
+    units.insert(1, ());
+    units.insert(1, ());
+    units.swap(0, 2);
+    assert_eq!((units[2], units.remove(1), units.remove(2)), ((), Some(()), None));
+    assert_eq!(units.len(), 2);
+  }
+
+  #[test]
+  #[should_panic(expected = "index out of bounds: the len is 1 but the index is 1")]
+  fn index_out_of_bounds() {
+    let mut list = List::new();
+    list.push(1);
+    list[1] += 1;
+  }
+
+  #[test]
+  fn shared_iter() {
+    let mut list = List::new();
+    assert_eq!(list.iter().next(), None);
+    assert_eq!(list.iter().next_back(), None);
+    for i in 1..=4 {
+      list.push(i);
+    }
+
+    // 两个共享迭代器交替前进
+    let shared = &list;
+    let mut a = shared.iter();
+    let mut b = shared.iter().rev();
+    assert_eq!((a.next(), b.next()), (Some(&1), Some(&4)));
+    assert_eq!((a.next(), b.next()), (Some(&2), Some(&3)));
+    assert_eq!(a.len(), 2);
+    let c = a.clone();
+    assert_eq!(a.collect::<Vec<_>>(), vec![&3, &4]);
+    assert_eq!(c.rev().collect::<Vec<_>>(), vec![&4, &3]);
+
+    // 两头相遇之后不会再交出元素，也不会重新开始
+    let mut iter = list.iter();
+    assert_eq!(iter.next(), Some(&1));
+    assert_eq!(iter.next_back(), Some(&4));
+    assert_eq!(iter.next_back(), Some(&3));
+    assert_eq!(iter.next(), Some(&2));
+    assert_eq!(iter.len(), 0);
+    assert_eq!((iter.next(), iter.next_back(), iter.next()), (None, None, None));
+
+    // 共享迭代器结束之后再可变借用
+    for x in &mut list {
+      *x *= 10;
+    }
+    if let Some(x) = list.iter_mut().next_back() {
+      *x += 1;
+    }
+    assert_eq!((&list).into_iter().copied().collect::<Vec<_>>(), vec![10, 20, 30, 41]);
+
+    // pop 之后 prev 指针和长度都要跟着变
+    assert_eq!(list.pop(), Some(10));
+    list.push(50);
+    assert_eq!(list.len(), 4);
+    assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), vec![50, 41, 30, 20]);
+    assert_eq!(list.into_iter().len(), 4);
+  }
+
+  #[test]
+  fn shared_iter_across_threads() {
+    let mut list = List::new();
+    for i in 0..100 {
+      list.push(i);
+    }
+    std::thread::scope(|s| {
+      let forward = s.spawn(|| list.iter().sum::<i32>());
+      let backward = s.spawn(|| list.iter().rev().take(10).sum::<i32>());
+      assert_eq!(forward.join().unwrap(), 4950);
+      assert_eq!(backward.join().unwrap(), 945);
+    });
+  }
+
+  // IterMut 被送到别的线程里改元素，Miri 会检查这期间有没有别的访问和它冲突
+  #[test]
+  fn iter_mut_across_threads() {
+    let mut list = List::new();
+    for i in 0..20 {
+      list.push(i);
+    }
+    let mut iter = list.iter_mut();
+    let front: Vec<_> = iter.by_ref().take(10).collect();
+    std::thread::scope(|s| {
+      s.spawn(move || front.into_iter().for_each(|x| *x += 100));
+      s.spawn(move || iter.for_each(|x| *x *= 2));
+    });
+    let expected: Vec<_> = (0..10).map(|i| i + 100).chain((10..20).map(|i| i * 2)).collect();
+    assert_eq!(list.iter().copied().collect::<Vec<_>>(), expected);
+
+    // 整个链表送过去再送回来，节点的所有权跟着走
+    let list = std::thread::spawn(move || {
+      list.pop();
+      list
+    }).join().unwrap();
+    assert_eq!(list.len(), 19);
+  }
+
+  // 协变之后的链表照常使用，让 Miri 检查转换后的节点指针
+  #[test]
+  fn covariant_list_in_use() {
+    static A: i32 = 1;
+    static B: i32 = 2;
+    let mut long: List<&'static i32> = List::new();
+    long.push(&A);
+    long.push(&B);
+    let empty: List<&'static i32> = List::new();
+    let local = 3;
+    let (mut short, mut iter, into_iter) = covariance(long, empty.iter(), List::new().into_iter());
+    assert_eq!((iter.next(), into_iter.len()), (None, 0));
+    short.push(&local);
+    short.push_front(&0);
+    assert_eq!(short.iter().map(|x| **x).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+    assert_eq!(short.pop_back(), Some(&3));
+  }
+
+  // 能编译通过就说明协变，对应的反例在 List 的文档里（compile_fail）
+  fn covariance<'i, 'a, T>(
+    list: List<&'static T>,
+    iter: Iter<'i, &'static T>,
+    into_iter: IntoIter<&'static T>,
+  ) -> (List<&'a T>, Iter<'i, &'a T>, IntoIter<&'a T>) {
+    (list, iter, into_iter)
+  }
+
+  #[test]
+  fn auto_traits() {
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    assert_send::<List<i32>>();
+    assert_sync::<List<i32>>();
+    assert_send::<Iter<'static, i32>>();
+    assert_sync::<Iter<'static, i32>>();
+    assert_send::<IterMut<'static, i32>>();
+    assert_sync::<IterMut<'static, i32>>();
+    assert_send::<IntoIter<i32>>();
+    assert_sync::<IntoIter<i32>>();
+    // Cell 是 Send 但不是 Sync，List<Cell<_>> 也一样（Sync 的反例在文档里）
+    assert_send::<List<std::cell::Cell<i32>>>();
+  }
+
+  #[test]
+  fn move_to_thread() {
+    let mut list: List<&'static str> = List::new();
+    list.push("a");
+    list.push("b");
+    let handle = std::thread::spawn(move || list.into_iter().collect::<Vec<_>>());
+    assert_eq!(handle.join().unwrap(), vec!["a", "b"]);
+
+    // 协变：长生命周期的 List 可以放进短生命周期的位置
+    let owned = String::from("short");
+    let mut short: List<&str> = List::new();
+    short.push(&owned);
+    let long: List<&'static str> = List::new();
+    let mut lists = [short, long];
+    lists[1].push(&owned);
+    assert_eq!(lists[1].peek(), Some(&"short"));
+  }
 }
\ No newline at end of file
@@ -0,0 +1,38 @@
+// 几个链表共用的小工具
+
+use std::mem;
+use std::ptr::NonNull;
+
+// Drop 里用 pop 逐个取出元素再丢掉，而不是递归地 drop 节点。
+// 元素的 drop 可能 panic，这时 DropGuard 在栈展开时接着 pop，剩下的节点不会泄漏。
+// pop 要在交出元素之前把链表改成一致的状态
+pub(crate) fn pop_all<L, T>(list: &mut L, pop: fn(&mut L) -> Option<T>) {
+  struct DropGuard<'a, L, T> {
+    list: &'a mut L,
+    pop: fn(&mut L) -> Option<T>,
+  }
+
+  impl<L, T> Drop for DropGuard<'_, L, T> {
+    fn drop(&mut self) {
+      while (self.pop)(self.list).is_some() {}
+    }
+  }
+
+  let guard = DropGuard { list, pop };
+  while let Some(elem) = (guard.pop)(guard.list) {
+    drop(elem);
+  }
+}
+
+// T 是零大小类型（()、标记类型）时，stack_ok 和 unsafe_deque_ok 不分配节点，只靠 len 记数
+pub(crate) const fn is_zst<T>() -> bool {
+  mem::size_of::<T>() == 0
+}
+
+// 零大小类型的值从哪里读出来都一样，引用直接指向对齐的悬垂指针
+//
+// Safety: T 必须是零大小类型
+pub(crate) unsafe fn zst<'a, T>() -> &'a mut T {
+  debug_assert!(is_zst::<T>());
+  &mut *NonNull::dangling().as_ptr()
+}